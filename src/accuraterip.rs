@@ -0,0 +1,753 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Support for [AccurateRip](http://www.accuraterip.com/).
+//!
+//! AccurateRip identifies a disc by three IDs, which are calculated from the TOC. See
+//! [`AccurateRipId`] for details.
+//!
+//! To verify a rip, AccurateRip compares checksums calculated over the audio data of each
+//! track. [`track_checksum`] and [`disc_checksums`] calculate these checksums from 16 bit
+//! stereo PCM data, e.g. the content of a BIN file or the data chunk of a WAV file.
+//!
+//! The AccurateRip database returns the checksums submitted for a disc as `dBAR-*.bin` file.
+//! [`DbarFile`] parses such a file and verifies the checksums calculated for a rip.
+//!
+//! [`AccurateRipId`]: ./struct.AccurateRipId.html
+//! [`track_checksum`]: ./fn.track_checksum.html
+//! [`disc_checksums`]: ./fn.disc_checksums.html
+//! [`DbarFile`]: ./struct.DbarFile.html
+
+use crate::native;
+use crate::toc::{DATA_TRACK_GAP, LEAD_IN};
+use crate::{DiscError, Toc, Track};
+use std::fmt;
+use std::io::{self, ErrorKind, Read};
+
+/// Number of stereo samples per sector.
+const SAMPLES_PER_SECTOR: u32 = 588;
+
+/// Number of bytes per sector of 16 bit stereo PCM data.
+const BYTES_PER_SECTOR: usize = 2352;
+
+/// Number of sectors at the start of the first and the end of the last track, which are not
+/// part of the checksum. Drives usually can not read these sectors reliably with a read offset
+/// applied.
+const SKIPPED_SECTORS: u32 = 5;
+
+/// Number of sectors read at once while calculating checksums.
+const SECTORS_PER_READ: usize = 64;
+
+/// The base URL of the AccurateRip database.
+const BASE_URL: &str = "http://www.accuraterip.com/accuraterip/";
+
+/// The AccurateRip disc ID.
+///
+/// The ID consists of three parts:
+///
+/// - `id1`: The sum of the start offsets of all audio tracks and the lead-out.
+/// - `id2`: The sum of the start offsets of all audio tracks multiplied by the track number
+///   and the lead-out multiplied by the number of audio tracks plus one.
+/// - `freedb_id`: The FreeDB disc ID of the whole disc.
+///
+/// All offsets are given without the 150 sectors lead-in.
+///
+/// # Examples
+///
+/// ```
+/// use discid::accuraterip::AccurateRipId;
+/// use discid::Toc;
+///
+/// let toc: Toc = "1 2 39738 150 18901".parse().expect("parsing TOC failed");
+/// let ar_id = AccurateRipId::from_toc(&toc);
+/// assert_eq!("0000e3e3-0002626b-0b020f02", ar_id.to_string());
+/// assert_eq!("dBAR-002-0000e3e3-0002626b-0b020f02.bin", ar_id.file_name());
+/// assert_eq!("3/e/3/dBAR-002-0000e3e3-0002626b-0b020f02.bin", ar_id.path());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AccurateRipId {
+    /// The sum of the track offsets.
+    pub id1: u32,
+
+    /// The sum of the track offsets weighted by the track numbers.
+    pub id2: u32,
+
+    /// The FreeDB disc ID of the whole disc, including data tracks.
+    pub freedb_id: u32,
+
+    /// The number of audio tracks.
+    pub audio_tracks: i32,
+}
+
+impl AccurateRipId {
+    /// Calculates the AccurateRip disc ID for an audio CD.
+    ///
+    /// For an enhanced CD, where the last track is a data track, the TOC as reported by
+    /// libdiscid does not include the length of the data track, which is required to
+    /// calculate the ID. Use [`from_enhanced_cd`](#method.from_enhanced_cd) in this case.
+    pub fn from_toc(toc: &Toc) -> AccurateRipId {
+        let freedb_id =
+            native::freedb_id_value(toc.first_track(), toc.last_track(), &toc.full_offsets());
+        AccurateRipId::calculate(toc, toc.lead_out(), freedb_id)
+    }
+
+    /// Calculates the AccurateRip disc ID for an enhanced CD.
+    ///
+    /// `toc` is the TOC of the audio session, as reported by libdiscid. The data track is
+    /// expected to start 11400 sectors after the lead-out of `toc`, and `disc_lead_out` is
+    /// the offset of the lead-out after the data track, i.e. the end of the disc.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use discid::accuraterip::AccurateRipId;
+    /// use discid::Toc;
+    ///
+    /// let toc: Toc = "1 2 39738 150 18901".parse().expect("parsing TOC failed");
+    /// let ar_id = AccurateRipId::from_enhanced_cd(&toc, 60138).expect("invalid TOC");
+    /// assert_eq!("00013393-0003517b-1a031f03", ar_id.to_string());
+    /// assert_eq!("dBAR-002-00013393-0003517b-1a031f03.bin", ar_id.file_name());
+    /// ```
+    pub fn from_enhanced_cd(toc: &Toc, disc_lead_out: i32) -> Result<AccurateRipId, DiscError> {
+        let mut offsets = vec![disc_lead_out];
+        offsets.extend_from_slice(toc.offsets());
+        offsets.push(toc.lead_out() + DATA_TRACK_GAP);
+        let (last, full_offsets) = native::full_offsets(toc.first_track(), &offsets)?;
+        let freedb_id = native::freedb_id_value(toc.first_track(), last, &full_offsets);
+        Ok(AccurateRipId::calculate(toc, disc_lead_out, freedb_id))
+    }
+
+    fn calculate(toc: &Toc, disc_lead_out: i32, freedb_id: u32) -> AccurateRipId {
+        let mut id1: u32 = 0;
+        let mut id2: u32 = 0;
+        for (number, &offset) in (toc.first_track()..).zip(toc.offsets()) {
+            let lba = (offset - LEAD_IN) as u32;
+            id1 = id1.wrapping_add(lba);
+            id2 = id2.wrapping_add(lba.max(1).wrapping_mul(number as u32));
+        }
+        let lead_out = (disc_lead_out - LEAD_IN) as u32;
+        let audio_tracks = toc.track_count();
+        id1 = id1.wrapping_add(lead_out);
+        id2 = id2.wrapping_add(lead_out.max(1).wrapping_mul(audio_tracks as u32 + 1));
+        AccurateRipId {
+            id1,
+            id2,
+            freedb_id,
+            audio_tracks,
+        }
+    }
+
+    /// The name of the file containing the AccurateRip data for this disc.
+    ///
+    /// The file name has the format `dBAR-ttt-xxxxxxxx-yyyyyyyy-zzzzzzzz.bin`, where `ttt` is
+    /// the number of audio tracks, followed by the three parts of the disc ID.
+    pub fn file_name(&self) -> String {
+        format!(
+            "dBAR-{:03}-{:08x}-{:08x}-{:08x}.bin",
+            self.audio_tracks, self.id1, self.id2, self.freedb_id
+        )
+    }
+
+    /// The path of the file containing the AccurateRip data relative to the database root.
+    ///
+    /// The file is stored in subdirectories named after the last three hexadecimal digits of
+    /// `id1`, in reverse order.
+    pub fn path(&self) -> String {
+        format!(
+            "{:x}/{:x}/{:x}/{}",
+            self.id1 & 0xf,
+            self.id1 >> 4 & 0xf,
+            self.id1 >> 8 & 0xf,
+            self.file_name()
+        )
+    }
+
+    /// The URL of the file containing the AccurateRip data for this disc.
+    pub fn url(&self) -> String {
+        format!("{}{}", BASE_URL, self.path())
+    }
+}
+
+impl fmt::Display for AccurateRipId {
+    /// Formats the disc ID as `xxxxxxxx-yyyyyyyy-zzzzzzzz`, the format used in the logs of
+    /// EAC and XLD.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:08x}-{:08x}-{:08x}",
+            self.id1, self.id2, self.freedb_id
+        )
+    }
+}
+
+/// The AccurateRip checksums of a track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TrackChecksum {
+    /// The AccurateRip v1 checksum.
+    pub v1: u32,
+
+    /// The AccurateRip v2 checksum.
+    pub v2: u32,
+}
+
+/// Calculates the AccurateRip checksums of a single track.
+///
+/// `reader` must provide the audio data of the track as 16 bit little-endian stereo PCM,
+/// without any file headers, and `sectors` is the length of the track in sectors. The data is
+/// read in small blocks, so the track does not need to fit into memory.
+///
+/// The first 5 sectors of the first track and the last 5 sectors of the
+/// last track are not part of the checksum. Set `first` and `last` accordingly.
+///
+/// Fails with `ErrorKind::UnexpectedEof` if the reader provides less data than required and
+/// with `ErrorKind::InvalidInput` if the track has more samples than fit into 32 bit, as
+/// required by the checksum algorithm.
+///
+/// # Examples
+///
+/// ```
+/// use discid::accuraterip::track_checksum;
+///
+/// let silence = vec![0u8; 2352 * 10];
+/// let checksum = track_checksum(&silence[..], 10, true, true).expect("reading failed");
+/// assert_eq!(0, checksum.v1);
+/// assert_eq!(0, checksum.v2);
+/// ```
+pub fn track_checksum<R: Read>(
+    mut reader: R,
+    sectors: i32,
+    first: bool,
+    last: bool,
+) -> io::Result<TrackChecksum> {
+    let total_samples = (sectors.max(0) as u32)
+        .checked_mul(SAMPLES_PER_SECTOR)
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "Track too long"))?;
+    let check_start = if first {
+        SKIPPED_SECTORS * SAMPLES_PER_SECTOR
+    } else {
+        0
+    };
+    let check_end = if last {
+        total_samples.saturating_sub(SKIPPED_SECTORS * SAMPLES_PER_SECTOR)
+    } else {
+        total_samples
+    };
+
+    let mut checksum = TrackChecksum { v1: 0, v2: 0 };
+    let mut buffer = vec![0; SECTORS_PER_READ * BYTES_PER_SECTOR];
+    let mut multiplier: u32 = 1;
+    let mut remaining = sectors.max(0) as usize;
+    while remaining > 0 {
+        let count = remaining.min(SECTORS_PER_READ);
+        let data = &mut buffer[..count * BYTES_PER_SECTOR];
+        reader.read_exact(data)?;
+        for sample in data.chunks_exact(4) {
+            if multiplier >= check_start && multiplier <= check_end {
+                let value = u32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]);
+                checksum.v1 = checksum.v1.wrapping_add(value.wrapping_mul(multiplier));
+                let product = u64::from(value) * u64::from(multiplier);
+                checksum.v2 = checksum
+                    .v2
+                    .wrapping_add((product >> 32) as u32)
+                    .wrapping_add(product as u32);
+            }
+            multiplier += 1;
+        }
+        remaining -= count;
+    }
+    Ok(checksum)
+}
+
+/// Calculates the AccurateRip checksums of all tracks of a disc.
+///
+/// `reader` must provide the audio data of the whole disc as 16 bit little-endian stereo PCM,
+/// starting at the offset of the first track, as it is stored in a single file disc image.
+/// The track boundaries are taken from the [`offset`] and [`sectors`] of `tracks`, e.g. as
+/// returned by [`DiscInfo::tracks`] or collected from [`DiscId::tracks`].
+///
+/// Returns the checksums in the order of `tracks`.
+///
+/// [`offset`]: ../struct.Track.html#structfield.offset
+/// [`sectors`]: ../struct.Track.html#structfield.sectors
+/// [`DiscInfo::tracks`]: ../struct.DiscInfo.html#method.tracks
+/// [`DiscId::tracks`]: ../struct.DiscId.html#method.tracks
+pub fn disc_checksums<R: Read>(mut reader: R, tracks: &[Track]) -> io::Result<Vec<TrackChecksum>> {
+    let mut checksums = Vec::with_capacity(tracks.len());
+    let mut position = tracks.first().map_or(0, |track| track.offset);
+    for (i, track) in tracks.iter().enumerate() {
+        // Skip any gap between the tracks, which is not part of any checksum.
+        let gap =
+            (i64::from(track.offset) - i64::from(position)).max(0) as u64 * BYTES_PER_SECTOR as u64;
+        io::copy(&mut reader.by_ref().take(gap), &mut io::sink())?;
+        let checksum = track_checksum(
+            reader.by_ref(),
+            track.sectors,
+            i == 0,
+            i + 1 == tracks.len(),
+        )?;
+        checksums.push(checksum);
+        position = track.offset.saturating_add(track.sectors);
+    }
+    Ok(checksums)
+}
+
+/// The version of an AccurateRip checksum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChecksumVersion {
+    /// The original AccurateRip checksum.
+    V1,
+
+    /// The AccurateRip v2 checksum, which also covers the upper bits of the product.
+    V2,
+}
+
+/// A parsed AccurateRip `dBAR-*.bin` file.
+///
+/// The file contains one entry for each set of submitted checksums. Different pressings of
+/// the same disc usually have separate entries, and v1 and v2 checksums are submitted as
+/// separate entries as well.
+///
+/// # Examples
+///
+/// ```
+/// use discid::accuraterip::{DbarFile, TrackChecksum, TrackVerification};
+///
+/// // A single entry for a disc with one track.
+/// let data = [
+///     1, 0x2a, 0, 0, 0, 0x2b, 0, 0, 0, 0x01, 0, 0, 0x01, // header
+///     12, 0x78, 0x56, 0x34, 0x12, 0, 0, 0, 0, // track 1
+/// ];
+/// let dbar = DbarFile::parse(&data).expect("parsing dBAR file failed");
+///
+/// let checksums = [TrackChecksum { v1: 0x12345678, v2: 0x9abcdef0 }];
+/// let result = dbar.verify(&checksums);
+/// assert_eq!(Some(0), result.pressing);
+/// assert_eq!("accurately ripped (confidence 12)", result.tracks[0].to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbarFile {
+    /// All entries of the file, in order.
+    pub entries: Vec<DbarEntry>,
+}
+
+/// An entry of a [`DbarFile`].
+///
+/// [`DbarFile`]: ./struct.DbarFile.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbarEntry {
+    /// The disc ID of the entry.
+    pub id: AccurateRipId,
+
+    /// The submitted data for each track.
+    pub tracks: Vec<DbarTrack>,
+}
+
+/// The submitted data for a single track of a [`DbarEntry`].
+///
+/// [`DbarEntry`]: ./struct.DbarEntry.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DbarTrack {
+    /// The number of submissions with this checksum.
+    pub confidence: u8,
+
+    /// The checksum of the track, either v1 or v2.
+    pub crc: u32,
+
+    /// The checksum of sector 450 of the track, used to detect the drive offset.
+    pub frame450_crc: u32,
+}
+
+/// The result of verifying a track against a [`DbarFile`].
+///
+/// [`DbarFile`]: ./struct.DbarFile.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrackVerification {
+    /// The checksum of the track matches an entry.
+    Accurate {
+        /// The number of submissions with this checksum.
+        confidence: u8,
+        /// The version of the matching checksum.
+        version: ChecksumVersion,
+        /// The index of the matching entry in [`DbarFile::entries`].
+        ///
+        /// [`DbarFile::entries`]: ./struct.DbarFile.html#structfield.entries
+        entry: usize,
+    },
+
+    /// The checksum of the track does not match any entry.
+    NoMatch,
+}
+
+/// The result of verifying a disc against a [`DbarFile`].
+///
+/// [`DbarFile`]: ./struct.DbarFile.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscVerification {
+    /// The result for each track, in the order of the given checksums.
+    pub tracks: Vec<TrackVerification>,
+
+    /// The index of the entry in [`DbarFile::entries`] which matches the most tracks, if any
+    /// track matched. This identifies the pressing of the ripped disc.
+    ///
+    /// [`DbarFile::entries`]: ./struct.DbarFile.html#structfield.entries
+    pub pressing: Option<usize>,
+}
+
+impl DbarFile {
+    /// Parses the content of a `dBAR-*.bin` file.
+    ///
+    /// Each entry consists of a 13 byte header with the number of tracks and the three parts
+    /// of the disc ID, followed by 9 bytes for each track with the confidence, the checksum
+    /// and the checksum of sector 450. All numbers are little-endian.
+    pub fn parse(data: &[u8]) -> Result<DbarFile, DiscError> {
+        let mut entries = Vec::new();
+        let mut position = 0;
+        let truncated = |position: usize| {
+            DiscError::parse(
+                0,
+                &format!("Unexpected end of dBAR file at byte {}", position),
+            )
+        };
+        while position < data.len() {
+            let header = data
+                .get(position..position + 13)
+                .ok_or_else(|| truncated(position))?;
+            let track_count = header[0];
+            let id = AccurateRipId {
+                id1: read_u32(&header[1..]),
+                id2: read_u32(&header[5..]),
+                freedb_id: read_u32(&header[9..]),
+                audio_tracks: i32::from(track_count),
+            };
+            position += 13;
+
+            let mut tracks = Vec::with_capacity(usize::from(track_count));
+            for _ in 0..track_count {
+                let track = data
+                    .get(position..position + 9)
+                    .ok_or_else(|| truncated(position))?;
+                tracks.push(DbarTrack {
+                    confidence: track[0],
+                    crc: read_u32(&track[1..]),
+                    frame450_crc: read_u32(&track[5..]),
+                });
+                position += 9;
+            }
+            entries.push(DbarEntry { id, tracks });
+        }
+        Ok(DbarFile { entries })
+    }
+
+    /// Verifies the checksums calculated for the tracks of a disc.
+    ///
+    /// `checksums` must contain the checksums of all audio tracks in order, as returned by
+    /// [`disc_checksums`]. Only entries with the same number of tracks are considered. If a
+    /// track matches several entries, the match with the highest confidence is reported.
+    ///
+    /// [`disc_checksums`]: ./fn.disc_checksums.html
+    pub fn verify(&self, checksums: &[TrackChecksum]) -> DiscVerification {
+        let entries: Vec<(usize, &DbarEntry)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.tracks.len() == checksums.len())
+            .collect();
+
+        let mut matches_per_entry = vec![0; self.entries.len()];
+        let mut tracks = Vec::with_capacity(checksums.len());
+        for (i, checksum) in checksums.iter().enumerate() {
+            let mut result = TrackVerification::NoMatch;
+            for &(index, entry) in entries.iter() {
+                let track = &entry.tracks[i];
+                let version = if track.crc == checksum.v2 {
+                    ChecksumVersion::V2
+                } else if track.crc == checksum.v1 {
+                    ChecksumVersion::V1
+                } else {
+                    continue;
+                };
+                matches_per_entry[index] += 1;
+                let better = match result {
+                    TrackVerification::Accurate { confidence, .. } => track.confidence > confidence,
+                    TrackVerification::NoMatch => true,
+                };
+                if better {
+                    result = TrackVerification::Accurate {
+                        confidence: track.confidence,
+                        version,
+                        entry: index,
+                    };
+                }
+            }
+            tracks.push(result);
+        }
+
+        let pressing = matches_per_entry
+            .iter()
+            .enumerate()
+            .filter(|(_, &matches)| matches > 0)
+            .max_by_key(|(index, &matches)| (matches, std::cmp::Reverse(*index)))
+            .map(|(index, _)| index);
+        DiscVerification { tracks, pressing }
+    }
+}
+
+impl fmt::Display for TrackVerification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TrackVerification::Accurate { confidence, .. } => {
+                write!(f, "accurately ripped (confidence {})", confidence)
+            }
+            TrackVerification::NoMatch => write!(f, "no match"),
+        }
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        disc_checksums, track_checksum, AccurateRipId, ChecksumVersion, DbarFile, DbarTrack,
+        TrackChecksum, TrackVerification,
+    };
+    use crate::{DiscError, Toc, TocErrorKind, Track, TrackIsrc};
+    use std::io::ErrorKind;
+
+    #[test]
+    fn accuraterip_id() {
+        let toc: Toc = "1 10 206535 150 18901 39738 59557 79152 100126 124833 147278 166336 182560"
+            .parse()
+            .unwrap();
+        let ar_id = AccurateRipId::from_toc(&toc);
+        assert_eq!(0x0011_24bc, ar_id.id1);
+        assert_eq!(0x0089_c3df, ar_id.id2);
+        assert_eq!(0x830a_bf0a, ar_id.freedb_id);
+        assert_eq!(10, ar_id.audio_tracks);
+        assert_eq!("001124bc-0089c3df-830abf0a", ar_id.to_string());
+        assert_eq!(
+            "c/b/4/dBAR-010-001124bc-0089c3df-830abf0a.bin",
+            ar_id.path()
+        );
+        assert_eq!(
+            "http://www.accuraterip.com/accuraterip/c/b/4/dBAR-010-001124bc-0089c3df-830abf0a.bin",
+            ar_id.url()
+        );
+    }
+
+    #[test]
+    fn accuraterip_id_first_track_not_one() {
+        // Offsets are weighted by the actual track number.
+        let toc: Toc = "3 4 2150 150 900".parse().unwrap();
+        let ar_id = AccurateRipId::from_toc(&toc);
+        assert_eq!(750 + 2000, ar_id.id1);
+        assert_eq!(3 + 750 * 4 + 2000 * 3, ar_id.id2);
+    }
+
+    #[test]
+    fn accuraterip_id_enhanced_cd() {
+        let toc: Toc = "1 2 39738 150 18901".parse().unwrap();
+        let ar_id = AccurateRipId::from_enhanced_cd(&toc, 60138).unwrap();
+        assert_eq!(18751 + 59988, ar_id.id1);
+        assert_eq!(1 + 18751 * 2 + 59988 * 3, ar_id.id2);
+        assert_eq!(0x1a03_1f03, ar_id.freedb_id);
+        assert_eq!(2, ar_id.audio_tracks);
+
+        assert_eq!(
+            Err(DiscError::invalid_toc(
+                TocErrorKind::InvalidOffset,
+                "Invalid offset"
+            )),
+            AccurateRipId::from_enhanced_cd(&toc, 50000)
+        );
+    }
+
+    /// Creates pseudo random test PCM data.
+    fn test_data(sectors: usize) -> Vec<u8> {
+        (0..sectors as u32 * 588)
+            .flat_map(|i| i.wrapping_mul(0x9e37_79b9).to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn accuraterip_track_checksum() {
+        let data = [1u8, 0, 0, 0].repeat(588);
+        let checksum = track_checksum(&data[..], 1, false, false).unwrap();
+        assert_eq!(588 * 589 / 2, checksum.v1);
+        assert_eq!(588 * 589 / 2, checksum.v2);
+
+        let data = test_data(20);
+        let checksum = track_checksum(&data[..], 20, false, false).unwrap();
+        assert_eq!(
+            TrackChecksum {
+                v1: 0xb0fc_ef30,
+                v2: 0xb30c_77a5,
+            },
+            checksum
+        );
+    }
+
+    #[test]
+    fn accuraterip_track_checksum_skip() {
+        let data = [1u8, 0, 0, 0].repeat(588 * 11);
+        let sum = |from: u32, to: u32| (from..=to).sum::<u32>();
+        let checksum = track_checksum(&data[..], 11, true, false).unwrap();
+        assert_eq!(sum(2940, 588 * 11), checksum.v1);
+        let checksum = track_checksum(&data[..], 11, false, true).unwrap();
+        assert_eq!(sum(1, 588 * 6), checksum.v1);
+        let checksum = track_checksum(&data[..], 11, true, true).unwrap();
+        assert_eq!(sum(2940, 588 * 6), checksum.v1);
+    }
+
+    #[test]
+    fn accuraterip_track_checksum_known_answer() {
+        // Calculated with the reference algorithm, which sums the samples 2940 (1-based)
+        // up to the 5th last sector for a track which is both the first and the last.
+        let data = test_data(20);
+        let checksum = track_checksum(&data[..], 20, true, true).unwrap();
+        assert_eq!(
+            TrackChecksum {
+                v1: 0x8fb4_fb0c,
+                v2: 0x90bc_d219,
+            },
+            checksum
+        );
+    }
+
+    #[test]
+    fn accuraterip_track_checksum_eof() {
+        let data = vec![0; 2352 * 2];
+        let error = track_checksum(&data[..], 3, false, false).unwrap_err();
+        assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+    }
+
+    #[test]
+    fn accuraterip_track_checksum_too_long() {
+        let error = track_checksum(&[][..], 7_304_367, false, false).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        let error = track_checksum(&[][..], 7_304_366, false, false).unwrap_err();
+        assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+    }
+
+    #[test]
+    fn accuraterip_disc_checksums() {
+        let data = test_data(300);
+        let track = |number, offset, sectors| Track {
+            number,
+            offset,
+            sectors,
+            isrc: TrackIsrc::NotRead,
+        };
+        let tracks = vec![track(1, 150, 100), track(2, 250, 130), track(3, 380, 70)];
+        let checksums = disc_checksums(&data[..], &tracks).unwrap();
+        let expected = vec![
+            track_checksum(&data[..100 * 2352], 100, true, false).unwrap(),
+            track_checksum(&data[100 * 2352..230 * 2352], 130, false, false).unwrap(),
+            track_checksum(&data[230 * 2352..], 70, false, true).unwrap(),
+        ];
+        assert_eq!(expected, checksums);
+    }
+
+    /// Creates a dBAR entry for the given (confidence, crc) pairs.
+    fn dbar_entry(tracks: &[(u8, u32)]) -> Vec<u8> {
+        let mut data = vec![tracks.len() as u8];
+        data.extend_from_slice(&0x0011_24bcu32.to_le_bytes());
+        data.extend_from_slice(&0x0089_c3dfu32.to_le_bytes());
+        data.extend_from_slice(&0x830a_bf0au32.to_le_bytes());
+        for &(confidence, crc) in tracks {
+            data.push(confidence);
+            data.extend_from_slice(&crc.to_le_bytes());
+            data.extend_from_slice(&0xdead_beefu32.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn accuraterip_dbar_parse() {
+        let mut data = dbar_entry(&[(5, 1), (6, 2)]);
+        data.extend(dbar_entry(&[(2, 3), (3, 4)]));
+        let dbar = DbarFile::parse(&data).unwrap();
+        assert_eq!(2, dbar.entries.len());
+        let entry = &dbar.entries[0];
+        assert_eq!(
+            "dBAR-002-001124bc-0089c3df-830abf0a.bin",
+            entry.id.file_name()
+        );
+        assert_eq!(
+            DbarTrack {
+                confidence: 6,
+                crc: 2,
+                frame450_crc: 0xdead_beef
+            },
+            entry.tracks[1]
+        );
+        assert_eq!(3, dbar.entries[1].tracks[0].crc);
+
+        assert!(DbarFile::parse(&[]).unwrap().entries.is_empty());
+        assert_eq!(
+            Err(DiscError::parse(
+                0,
+                "Unexpected end of dBAR file at byte 22"
+            )),
+            DbarFile::parse(&data[..30])
+        );
+        assert_eq!(
+            Err(DiscError::parse(
+                0,
+                "Unexpected end of dBAR file at byte 31"
+            )),
+            DbarFile::parse(&data[..35])
+        );
+    }
+
+    #[test]
+    fn accuraterip_dbar_verify() {
+        let mut data = dbar_entry(&[(5, 0x11), (6, 0x12), (7, 0x13)]);
+        data.extend(dbar_entry(&[(9, 0x21), (8, 0x22), (1, 0x23)]));
+        data.extend(dbar_entry(&[(2, 0x31), (3, 0x32)]));
+        let dbar = DbarFile::parse(&data).unwrap();
+
+        let checksums = [
+            TrackChecksum { v1: 0x11, v2: 0x21 },
+            TrackChecksum { v1: 0x12, v2: 0x99 },
+            TrackChecksum { v1: 0x98, v2: 0x99 },
+        ];
+        let result = dbar.verify(&checksums);
+        assert_eq!(
+            vec![
+                TrackVerification::Accurate {
+                    confidence: 9,
+                    version: ChecksumVersion::V2,
+                    entry: 1
+                },
+                TrackVerification::Accurate {
+                    confidence: 6,
+                    version: ChecksumVersion::V1,
+                    entry: 0
+                },
+                TrackVerification::NoMatch,
+            ],
+            result.tracks
+        );
+        assert_eq!(Some(0), result.pressing);
+        assert_eq!("no match", result.tracks[2].to_string());
+
+        let result = dbar.verify(&[TrackChecksum { v1: 0, v2: 0 }; 3]);
+        assert_eq!(vec![TrackVerification::NoMatch; 3], result.tracks);
+        assert_eq!(None, result.pressing);
+    }
+}
@@ -0,0 +1,549 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Support for the CDDB protocol used by FreeDB, gnudb and compatible servers.
+//!
+//! Discs are looked up in two steps: a `cddb query` command built from the TOC returns the
+//! matching database entries, each identified by a category and a disc ID. A `cddb read`
+//! command then returns the entry itself in xmcd format.
+//!
+//! This module does not implement any networking. [`CddbClient`] sends the commands through a
+//! [`CddbTransport`], which can be implemented on top of a TCP connection, the HTTP interface
+//! (see [`http_url`]) or an in-process fake server for tests.
+//!
+//! # Examples
+//!
+//! ```
+//! use discid::cddb::{CddbClient, QueryResponse};
+//! use discid::Toc;
+//! use std::io;
+//!
+//! // A fake server which knows a single disc.
+//! let server = |command: &str| -> io::Result<String> {
+//!     Ok(match command {
+//!         "cddb query 0b020f02 2 150 18901 529" => "200 rock 0b020f02 Artist / Album\r\n",
+//!         "cddb read rock 0b020f02" => "210 rock 0b020f02\r\nDTITLE=Artist / Album\r\n.\r\n",
+//!         _ => "500 Command syntax error\r\n",
+//!     }.to_string())
+//! };
+//!
+//! let toc: Toc = "1 2 39738 150 18901".parse().expect("parsing TOC failed");
+//! let mut client = CddbClient::new(server);
+//! match client.query(&toc).expect("query failed") {
+//!     QueryResponse::Match(found) => {
+//!         let entry = client.read(&found.category, &found.disc_id).expect("read failed");
+//!         assert_eq!("Album", entry.album());
+//!     }
+//!     _ => panic!("disc not found"),
+//! }
+//! ```
+//!
+//! [`CddbClient`]: ./struct.CddbClient.html
+//! [`CddbTransport`]: ./trait.CddbTransport.html
+//! [`http_url`]: ./fn.http_url.html
+
+use crate::msf::FRAMES_PER_SECOND;
+use crate::xmcd::XmcdEntry;
+use crate::{DiscError, Toc};
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// The CDDB protocol level requested by [`http_url`].
+///
+/// [`http_url`]: ./fn.http_url.html
+const PROTOCOL_LEVEL: u32 = 6;
+
+/// Sends CDDB commands to a server.
+///
+/// Implemented for all closures taking the command and returning the complete response,
+/// including the terminating `.` line of multi-line responses.
+pub trait CddbTransport {
+    /// Sends a command, e.g. `cddb query …`, and returns the response of the server.
+    fn send(&mut self, command: &str) -> io::Result<String>;
+}
+
+impl<F> CddbTransport for F
+where
+    F: FnMut(&str) -> io::Result<String>,
+{
+    fn send(&mut self, command: &str) -> io::Result<String> {
+        self(command)
+    }
+}
+
+/// The client identification sent with each request over HTTP.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CddbHello {
+    /// The login name of the user.
+    pub user: String,
+
+    /// The host name of the client.
+    pub host: String,
+
+    /// The name of the client application.
+    pub client: String,
+
+    /// The version of the client application.
+    pub version: String,
+}
+
+/// An error of a CDDB request.
+#[derive(Debug)]
+pub enum CddbError {
+    /// The transport failed to send the command or to receive the response.
+    Transport(io::Error),
+
+    /// The server returned an error or an unexpected status code.
+    Server {
+        /// The status code of the response.
+        code: u16,
+        /// The message of the response.
+        message: String,
+    },
+
+    /// The response could not be parsed.
+    Parse(DiscError),
+}
+
+/// A database entry found by a `cddb query` command.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CddbMatch {
+    /// The category of the entry, e.g. `rock`.
+    pub category: String,
+
+    /// The FreeDB disc ID of the entry.
+    pub disc_id: String,
+
+    /// The disc title of the entry, usually in the form `Artist / Album`.
+    pub title: String,
+}
+
+/// The response to a `cddb query` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryResponse {
+    /// Status 200: Exactly one exact match was found.
+    Match(CddbMatch),
+
+    /// Status 210: Several exact matches were found.
+    ExactMatches(Vec<CddbMatch>),
+
+    /// Status 211: No exact match, but close matches were found.
+    CloseMatches(Vec<CddbMatch>),
+
+    /// Status 202: No match was found.
+    NoMatch,
+}
+
+/// A client for CDDB servers, sending commands through a [`CddbTransport`].
+///
+/// [`CddbTransport`]: ./trait.CddbTransport.html
+#[derive(Debug)]
+pub struct CddbClient<T> {
+    transport: T,
+}
+
+impl<T: CddbTransport> CddbClient<T> {
+    /// Creates a client using the given transport.
+    pub fn new(transport: T) -> CddbClient<T> {
+        CddbClient { transport }
+    }
+
+    /// Looks up the disc with the given TOC.
+    ///
+    /// See [`query_command`] and [`parse_query_response`].
+    ///
+    /// [`query_command`]: ./fn.query_command.html
+    /// [`parse_query_response`]: ./fn.parse_query_response.html
+    pub fn query(&mut self, toc: &Toc) -> Result<QueryResponse, CddbError> {
+        let response = self.transport.send(&query_command(toc))?;
+        parse_query_response(&response)
+    }
+
+    /// Reads the database entry with the given category and disc ID.
+    ///
+    /// See [`read_command`] and [`parse_read_response`].
+    ///
+    /// [`read_command`]: ./fn.read_command.html
+    /// [`parse_read_response`]: ./fn.parse_read_response.html
+    pub fn read(&mut self, category: &str, disc_id: &str) -> Result<XmcdEntry, CddbError> {
+        let response = self.transport.send(&read_command(category, disc_id))?;
+        parse_read_response(&response)
+    }
+
+    /// Returns the transport of this client.
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+}
+
+/// Builds the `cddb query` command for a TOC.
+///
+/// The command has the form `cddb query discid ntrks off1 off2 … nsecs`, where `discid` is the
+/// FreeDB disc ID, followed by the number of tracks, the track offsets in sectors (including
+/// the 150 sectors lead-in) and the total length of the disc in seconds.
+///
+/// # Examples
+///
+/// ```
+/// use discid::cddb::query_command;
+/// use discid::Toc;
+///
+/// let toc: Toc = "1 2 39738 150 18901".parse().expect("parsing TOC failed");
+/// assert_eq!("cddb query 0b020f02 2 150 18901 529", query_command(&toc));
+/// ```
+pub fn query_command(toc: &Toc) -> String {
+    let mut command = format!("cddb query {} {}", toc.freedb_id(), toc.track_count());
+    for offset in toc.offsets() {
+        command.push_str(&format!(" {}", offset));
+    }
+    command.push_str(&format!(" {}", toc.lead_out() / FRAMES_PER_SECOND));
+    command
+}
+
+/// Builds the `cddb read` command for a database entry.
+pub fn read_command(category: &str, disc_id: &str) -> String {
+    format!("cddb read {} {}", category, disc_id)
+}
+
+/// Builds the URL for sending a command to the HTTP interface of a CDDB server.
+///
+/// `server` is the URL of the CGI script, e.g. `http://gnudb.gnudb.org/~cddb/cddb.cgi`.
+///
+/// # Examples
+///
+/// ```
+/// use discid::cddb::{http_url, CddbHello};
+///
+/// let hello = CddbHello {
+///     user: "user".to_string(),
+///     host: "example.com".to_string(),
+///     client: "discid".to_string(),
+///     version: "0.4".to_string(),
+/// };
+/// assert_eq!(
+///     "http://localhost/cddb.cgi?cmd=cddb+read+rock+0b020f02&hello=user+example.com+discid+0.4&proto=6",
+///     http_url("http://localhost/cddb.cgi", "cddb read rock 0b020f02", &hello)
+/// );
+/// ```
+pub fn http_url(server: &str, command: &str, hello: &CddbHello) -> String {
+    let hello = [&hello.user, &hello.host, &hello.client, &hello.version]
+        .iter()
+        .map(|value| encode(value))
+        .collect::<Vec<_>>()
+        .join("+");
+    format!(
+        "{}?cmd={}&hello={}&proto={}",
+        server,
+        encode(command),
+        hello,
+        PROTOCOL_LEVEL
+    )
+}
+
+/// Parses the response to a `cddb query` command.
+///
+/// Status codes other than 200, 202, 210 and 211 are returned as [`CddbError::Server`].
+///
+/// [`CddbError::Server`]: ./enum.CddbError.html#variant.Server
+pub fn parse_query_response(response: &str) -> Result<QueryResponse, CddbError> {
+    let (code, message, body) = split_response(response)?;
+    match code {
+        200 => Ok(QueryResponse::Match(parse_match(message, 1)?)),
+        202 => Ok(QueryResponse::NoMatch),
+        210 | 211 => {
+            let matches = body
+                .iter()
+                .map(|&(line_num, line)| parse_match(line, line_num))
+                .collect::<Result<_, _>>()?;
+            if code == 210 {
+                Ok(QueryResponse::ExactMatches(matches))
+            } else {
+                Ok(QueryResponse::CloseMatches(matches))
+            }
+        }
+        _ => Err(server_error(code, message)),
+    }
+}
+
+/// Parses the response to a `cddb read` command.
+///
+/// Status codes other than 210 are returned as [`CddbError::Server`].
+///
+/// [`CddbError::Server`]: ./enum.CddbError.html#variant.Server
+pub fn parse_read_response(response: &str) -> Result<XmcdEntry, CddbError> {
+    let (code, message, body) = split_response(response)?;
+    if code != 210 {
+        return Err(server_error(code, message));
+    }
+    let xmcd: Vec<&str> = body.iter().map(|&(_, line)| line).collect();
+    XmcdEntry::parse(&xmcd.join("\n")).map_err(|err| match err {
+        // Report line numbers relative to the whole response.
+        DiscError::Parse { line, message } if line > 0 => CddbError::Parse(DiscError::Parse {
+            line: line + 1,
+            message,
+        }),
+        err => CddbError::Parse(err),
+    })
+}
+
+/// A line of a response with its one based line number.
+type Line<'a> = (usize, &'a str);
+
+/// Splits a response into status code, message and the lines of a multi-line body.
+///
+/// The body ends at the terminating `.` line or the end of the response. Leading dots of
+/// body lines, which escape lines starting with a dot, are removed.
+fn split_response(response: &str) -> Result<(u16, &str, Vec<Line<'_>>), CddbError> {
+    let mut lines = response
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .enumerate()
+        .map(|(i, line)| (i + 1, line));
+    let status = lines
+        .next()
+        .map(|(_, line)| line)
+        .filter(|line| !line.is_empty())
+        .ok_or_else(|| CddbError::Parse(DiscError::parse(0, "Empty response")))?;
+    let (code, message) = match status.find(' ') {
+        Some(index) => (&status[..index], status[index + 1..].trim()),
+        None => (status, ""),
+    };
+    let code = code
+        .parse()
+        .map_err(|_| CddbError::Parse(DiscError::parse(1, "Invalid status code")))?;
+    let body = lines
+        .take_while(|&(_, line)| line != ".")
+        .map(|(line_num, line)| (line_num, line.strip_prefix('.').unwrap_or(line)))
+        .collect();
+    Ok((code, message, body))
+}
+
+/// Parses `category discid title`.
+fn parse_match(line: &str, line_num: usize) -> Result<CddbMatch, CddbError> {
+    let mut parts = line.trim().splitn(3, ' ');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(category), Some(disc_id), title) if !category.is_empty() => Ok(CddbMatch {
+            category: category.to_string(),
+            disc_id: disc_id.to_string(),
+            title: title.unwrap_or_default().trim().to_string(),
+        }),
+        _ => Err(CddbError::Parse(DiscError::parse(
+            line_num,
+            "Expected category and disc ID",
+        ))),
+    }
+}
+
+fn server_error(code: u16, message: &str) -> CddbError {
+    CddbError::Server {
+        code,
+        message: message.to_string(),
+    }
+}
+
+/// Encodes a value for the query string of the HTTP interface, spaces become `+`.
+fn encode(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b' ' => result.push('+'),
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                result.push(char::from(byte))
+            }
+            _ => result.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    result
+}
+
+impl Error for CddbError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CddbError::Transport(err) => Some(err),
+            CddbError::Parse(err) => Some(err),
+            CddbError::Server { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for CddbError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CddbError::Transport(err) => write!(f, "CddbError: {}", err),
+            CddbError::Server { code, message } => write!(f, "CddbError: {} {}", code, message),
+            CddbError::Parse(err) => write!(f, "CddbError: {}", err),
+        }
+    }
+}
+
+impl From<io::Error> for CddbError {
+    fn from(err: io::Error) -> CddbError {
+        CddbError::Transport(err)
+    }
+}
+
+impl From<DiscError> for CddbError {
+    fn from(err: DiscError) -> CddbError {
+        CddbError::Parse(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_query_response, parse_read_response, query_command, CddbClient, CddbError, CddbMatch,
+        QueryResponse,
+    };
+    use crate::{DiscError, Toc};
+    use std::io;
+
+    fn toc() -> Toc {
+        "1 10 206535 150 18901 39738 59557 79152 100126 124833 147278 166336 182560"
+            .parse()
+            .unwrap()
+    }
+
+    fn cddb_match(category: &str, title: &str) -> CddbMatch {
+        CddbMatch {
+            category: category.to_string(),
+            disc_id: "830abf0a".to_string(),
+            title: title.to_string(),
+        }
+    }
+
+    #[test]
+    fn cddb_query_command() {
+        assert_eq!(
+            "cddb query 830abf0a 10 150 18901 39738 59557 79152 100126 124833 147278 166336 182560 2753",
+            query_command(&toc())
+        );
+    }
+
+    #[test]
+    fn cddb_parse_query_response() {
+        assert_eq!(
+            QueryResponse::Match(cddb_match("rock", "Artist / Album")),
+            parse_query_response("200 rock 830abf0a Artist / Album\r\n").unwrap()
+        );
+        assert_eq!(
+            QueryResponse::NoMatch,
+            parse_query_response("202 No match found\r\n").unwrap()
+        );
+        assert_eq!(
+            QueryResponse::ExactMatches(vec![
+                cddb_match("rock", "Artist / Album"),
+                cddb_match("misc", "Artist / Album (Remaster)"),
+            ]),
+            parse_query_response(
+                "210 Found exact matches, list follows (until terminating `.')\r\n\
+                 rock 830abf0a Artist / Album\r\n\
+                 misc 830abf0a Artist / Album (Remaster)\r\n\
+                 .\r\n"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            QueryResponse::CloseMatches(vec![cddb_match("jazz", "")]),
+            parse_query_response(
+                "211 Found inexact matches, list follows (until terminating `.')\n\
+                 jazz 830abf0a\n\
+                 .\n"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn cddb_parse_query_response_errors() {
+        match parse_query_response("403 Database entry is corrupt\r\n") {
+            Err(CddbError::Server { code, message }) => {
+                assert_eq!(403, code);
+                assert_eq!("Database entry is corrupt", message);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        match parse_query_response("") {
+            Err(CddbError::Parse(err)) => assert_eq!(DiscError::parse(0, "Empty response"), err),
+            other => panic!("unexpected result {:?}", other),
+        }
+        match parse_query_response("OK\r\n") {
+            Err(CddbError::Parse(err)) => {
+                assert_eq!(DiscError::parse(1, "Invalid status code"), err)
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        match parse_query_response("211 Found inexact matches\r\nrock\r\n.\r\n") {
+            Err(CddbError::Parse(err)) => {
+                assert_eq!(DiscError::parse(2, "Expected category and disc ID"), err)
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cddb_parse_read_response() {
+        let entry = parse_read_response(
+            "210 rock 830abf0a CD database entry follows (until terminating `.')\r\n\
+             # xmcd\r\n\
+             DISCID=830abf0a\r\n\
+             DTITLE=Artist / Album\r\n\
+             TTITLE0=..And Then\r\n\
+             .\r\n",
+        )
+        .unwrap();
+        assert_eq!(vec!["830abf0a"], entry.disc_ids);
+        assert_eq!("Artist / Album", entry.title);
+        assert_eq!(vec!["..And Then"], entry.track_titles);
+
+        match parse_read_response("401 rock 830abf0a No such CD entry in database\r\n") {
+            Err(CddbError::Server { code: 401, .. }) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        match parse_read_response("210 rock 830abf0a\r\nDISCID=830abf0a\r\nDTITLE\r\n.\r\n") {
+            Err(CddbError::Parse(err)) => {
+                assert_eq!(DiscError::parse(3, "Expected KEYWORD=value"), err)
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cddb_client() {
+        let mut commands = Vec::new();
+        let server = |command: &str| -> io::Result<String> {
+            commands.push(command.to_string());
+            if command.starts_with("cddb query") {
+                Ok("210 Found exact matches\r\nrock 830abf0a Artist / Album\r\n.\r\n".to_string())
+            } else {
+                Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset"))
+            }
+        };
+        {
+            let mut client = CddbClient::new(server);
+            assert_eq!(
+                QueryResponse::ExactMatches(vec![cddb_match("rock", "Artist / Album")]),
+                client.query(&toc()).unwrap()
+            );
+            match client.read("rock", "830abf0a") {
+                Err(CddbError::Transport(err)) => {
+                    assert_eq!(io::ErrorKind::ConnectionReset, err.kind())
+                }
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+        assert_eq!("cddb read rock 830abf0a", commands[1]);
+    }
+}
@@ -0,0 +1,670 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Support for cdrdao TOC files.
+//!
+//! cdrdao describes the layout of a disc in a TOC file, listing the audio data of each track
+//! together with the track's pregap, ISRC and the disc's MCN. Use [`CdrdaoToc::parse`] to
+//! read a TOC file and [`CdrdaoToc::write`] to create one.
+//!
+//! Do not confuse a cdrdao TOC file with the [`Toc`] of a disc. The former describes the
+//! disc layout in terms of track lengths, the latter contains the track offsets used to
+//! calculate the disc ID. [`CdrdaoToc::toc`] converts between both.
+//!
+//! # Examples
+//!
+//! ```
+//! use discid::cdrdao::CdrdaoToc;
+//!
+//! let toc_file = CdrdaoToc::parse(r#"
+//! CD_DA
+//! CATALOG "0724385583222"
+//!
+//! // Track 1
+//! TRACK AUDIO
+//! ISRC "GBAYE9300106"
+//! FILE "data.wav" 0 04:10:01
+//!
+//! // Track 2
+//! TRACK AUDIO
+//! FILE "data.wav" 04:10:01 04:37:62
+//! START 00:01:25
+//! "#).expect("parsing cdrdao TOC file failed");
+//!
+//! let info = toc_file.disc_info().expect("invalid cdrdao TOC file");
+//! assert_eq!("1 2 39738 150 19001", info.toc_string());
+//! assert_eq!("0724385583222", info.mcn());
+//! assert_eq!("GBAYE9300106", info.nth_track(1).isrc.as_str());
+//! ```
+//!
+//! [`CdrdaoToc::parse`]: ./struct.CdrdaoToc.html#method.parse
+//! [`CdrdaoToc::write`]: ./struct.CdrdaoToc.html#method.write
+//! [`CdrdaoToc::toc`]: ./struct.CdrdaoToc.html#method.toc
+//! [`Toc`]: ../struct.Toc.html
+
+use crate::cue::tokenize;
+use crate::isrc;
+use crate::msf;
+use crate::toc::{DATA_TRACK_GAP, LEAD_IN};
+#[cfg(feature = "libdiscid")]
+use crate::DiscId;
+use crate::{DiscError, DiscInfo, Isrc, Toc, TocErrorKind};
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+/// Number of audio samples per sector.
+const SAMPLES_PER_SECTOR: u64 = 588;
+
+/// A parsed cdrdao TOC file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CdrdaoToc {
+    /// The media catalogue number given by the `CATALOG` statement, if present.
+    pub catalog: Option<String>,
+
+    /// All tracks, in order.
+    pub tracks: Vec<CdrdaoTrack>,
+}
+
+/// A single track of a [`CdrdaoToc`].
+///
+/// [`CdrdaoToc`]: ./struct.CdrdaoToc.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CdrdaoTrack {
+    /// The track mode, e.g. `AUDIO` or `MODE1`.
+    pub mode: String,
+
+    /// The ISRC given by the `ISRC` statement, if present.
+    pub isrc: Option<String>,
+
+    /// Length in sectors of the pregap, as given by the `START` or `PREGAP` statement.
+    ///
+    /// The pregap is part of the track's data, the track itself (index 1) starts after it.
+    pub pregap: i32,
+
+    /// Total length in sectors of the track's data, including the pregap.
+    ///
+    /// This is `None` if the length of a data source is not given in the TOC file, e.g. for
+    /// a `FILE` statement without length, which refers to the rest of the file.
+    pub length: Option<i32>,
+}
+
+impl CdrdaoTrack {
+    /// Returns true if this is an audio track.
+    pub fn is_audio(&self) -> bool {
+        self.mode.eq_ignore_ascii_case("AUDIO")
+    }
+}
+
+impl CdrdaoToc {
+    /// Parses a cdrdao TOC file.
+    ///
+    /// The statements `CATALOG`, `TRACK`, `ISRC`, `FILE`, `AUDIOFILE`, `DATAFILE`, `SILENCE`,
+    /// `ZERO`, `START` and `PREGAP` are evaluated. `CD_TEXT` blocks and all other statements
+    /// are ignored.
+    pub fn parse(toc_file: &str) -> Result<CdrdaoToc, DiscError> {
+        let mut result = CdrdaoToc {
+            catalog: None,
+            tracks: Vec::new(),
+        };
+
+        let mut block_depth = 0;
+        for (i, line) in toc_file.lines().enumerate() {
+            let line_num = i + 1;
+            let mut tokens = tokenize(line.trim_start_matches('\u{feff}'));
+            if let Some(comment) = tokens.iter().position(|t| t.starts_with("//")) {
+                tokens.truncate(comment);
+            }
+
+            if block_depth > 0 || tokens.iter().any(|t| t == "{") {
+                for token in tokens.iter() {
+                    match token.as_str() {
+                        "{" => block_depth += 1,
+                        "}" => block_depth -= 1,
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+
+            let command = match tokens.first() {
+                Some(command) => command.as_str(),
+                None => continue,
+            };
+            let argument = |n: usize| {
+                tokens.get(n).map(String::as_str).ok_or_else(|| {
+                    DiscError::parse(line_num, &format!("Missing argument for {}", command))
+                })
+            };
+
+            match command {
+                "CATALOG" => {
+                    let catalog = argument(1)?;
+                    if catalog.len() != 13 || !catalog.bytes().all(|b| b.is_ascii_digit()) {
+                        return Err(DiscError::parse(line_num, "Invalid CATALOG"));
+                    }
+                    result.catalog = Some(catalog.to_string());
+                }
+                "TRACK" => {
+                    if result.tracks.len() >= 99 {
+                        return Err(DiscError::parse(line_num, "Too many tracks"));
+                    }
+                    result.tracks.push(CdrdaoTrack {
+                        mode: argument(1)?.to_string(),
+                        isrc: None,
+                        pregap: 0,
+                        length: Some(0),
+                    });
+                }
+                "ISRC" => {
+                    let isrc = argument(1)?;
+                    if isrc.len() != 12 || !isrc.bytes().all(|b| b.is_ascii_alphanumeric()) {
+                        return Err(DiscError::parse(line_num, "Invalid ISRC"));
+                    }
+                    current_track(&mut result, line_num)?.isrc = Some(isrc.to_string());
+                }
+                "FILE" | "AUDIOFILE" => {
+                    // FILE "name" [#offset] start [length]
+                    let track = current_track(&mut result, line_num)?;
+                    let start = if argument(2)?.starts_with('#') { 3 } else { 2 };
+                    argument(start)?;
+                    let length = match tokens.get(start + 1) {
+                        Some(length) => Some(parse_time(length, line_num)?),
+                        None => None,
+                    };
+                    add_length(track, length, line_num)?;
+                }
+                "DATAFILE" => {
+                    // DATAFILE "name" [#offset] [length]
+                    let track = current_track(&mut result, line_num)?;
+                    let length = match tokens.get(2..).and_then(|args| args.last()) {
+                        Some(length) if !length.starts_with('#') => {
+                            Some(parse_time(length, line_num)?)
+                        }
+                        _ => None,
+                    };
+                    add_length(track, length, line_num)?;
+                }
+                "SILENCE" | "ZERO" => {
+                    // SILENCE length, ZERO [mode] length
+                    let track = current_track(&mut result, line_num)?;
+                    let length = parse_time(argument(tokens.len().max(2) - 1)?, line_num)?;
+                    add_length(track, Some(length), line_num)?;
+                }
+                "START" => {
+                    let track = current_track(&mut result, line_num)?;
+                    track.pregap = match tokens.get(1) {
+                        Some(start) => parse_time(start, line_num)?,
+                        None => track.length.ok_or_else(|| {
+                            DiscError::parse(line_num, "START requires the length of the data")
+                        })?,
+                    };
+                }
+                "PREGAP" => {
+                    let pregap = parse_time(argument(1)?, line_num)?;
+                    let track = current_track(&mut result, line_num)?;
+                    track.pregap = pregap;
+                    add_length(track, Some(pregap), line_num)?;
+                }
+                _ => {}
+            }
+        }
+
+        if result.tracks.is_empty() {
+            return Err(DiscError::parse(0, "TOC file contains no tracks"));
+        }
+        Ok(result)
+    }
+
+    /// Calculates the TOC of the disc described by this TOC file.
+    ///
+    /// The 150 sectors lead-in are added to all offsets. If the last track is a data track,
+    /// as it is the case for enhanced CDs, it is not part of the TOC and the lead-out is
+    /// placed 11400 sectors before the data track.
+    pub fn toc(&self) -> Result<Toc, DiscError> {
+        let tracks = self.audio_session_tracks();
+        let mut offsets = Vec::with_capacity(tracks.len());
+        let mut position = Some(LEAD_IN);
+        for (i, track) in self.tracks.iter().enumerate() {
+            let start = position
+                .ok_or_else(|| DiscError::parse(0, &format!("Unknown length of track {}", i)))?;
+            offsets.push(start.checked_add(track.pregap).ok_or_else(disc_too_long)?);
+            position = match track.length {
+                Some(length) => Some(start.checked_add(length).ok_or_else(disc_too_long)?),
+                None => None,
+            };
+            if i + 1 == tracks.len() {
+                break;
+            }
+        }
+
+        let lead_out = if tracks.len() < self.tracks.len() {
+            let start = position.ok_or_else(|| {
+                DiscError::parse(0, &format!("Unknown length of track {}", tracks.len()))
+            })?;
+            start
+                .checked_add(self.tracks[tracks.len()].pregap)
+                .ok_or_else(disc_too_long)?
+                - DATA_TRACK_GAP
+        } else {
+            position.ok_or_else(|| {
+                DiscError::parse(0, &format!("Unknown length of track {}", tracks.len()))
+            })?
+        };
+
+        Toc::new(1, offsets.len() as i32, lead_out, offsets)
+    }
+
+    /// Returns a [`DiscInfo`] for the disc described by this TOC file.
+    ///
+    /// In addition to the TOC, as calculated by [`toc`](#method.toc), the `DiscInfo`
+    /// contains the MCN and ISRCs given in the TOC file.
+    ///
+    /// [`DiscInfo`]: ../struct.DiscInfo.html
+    pub fn disc_info(&self) -> Result<DiscInfo, DiscError> {
+        let toc = self.toc()?;
+        let isrcs = self
+            .audio_session_tracks()
+            .iter()
+            .map(|track| track.isrc.clone())
+            .collect();
+        let mcn = self.catalog.clone().unwrap_or_default();
+        Ok(DiscInfo::from_parts(toc, mcn, isrc::track_isrcs(isrcs)))
+    }
+
+    /// Creates a cdrdao TOC file for the given disc.
+    ///
+    /// The `CATALOG` is set to the MCN and each track gets the ISRC, if they have been read
+    /// from the disc. Audio before the first track (hidden track one audio) becomes the
+    /// pregap of the first track. A first track starting before the end of the lead-in gets
+    /// no pregap.
+    ///
+    /// The TOC of a cdrdao TOC file always starts with track 1, hence discs starting with
+    /// another track number are renumbered.
+    pub fn from_disc_info(info: &DiscInfo) -> CdrdaoToc {
+        let tracks = info
+            .tracks()
+            .iter()
+            .enumerate()
+            .map(|(i, track)| {
+                let pregap = if i == 0 {
+                    (track.offset - LEAD_IN).max(0)
+                } else {
+                    0
+                };
+                CdrdaoTrack {
+                    mode: "AUDIO".to_string(),
+                    isrc: track.isrc.isrc().map(Isrc::to_string),
+                    pregap,
+                    length: Some(pregap + track.sectors),
+                }
+            })
+            .collect();
+
+        CdrdaoToc {
+            catalog: Some(info.mcn().to_string()).filter(|mcn| !mcn.is_empty()),
+            tracks,
+        }
+    }
+
+    /// Creates a cdrdao TOC file for a disc read with libdiscid.
+    ///
+    /// Read the disc with [`Features::MCN`] and [`Features::ISRC`] to include the MCN and
+    /// ISRCs. See [`from_disc_info`](#method.from_disc_info) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use discid::cdrdao::CdrdaoToc;
+    /// use discid::{DiscId, Features};
+    ///
+    /// let disc = DiscId::read_features(None, Features::all()).expect("Reading disc failed");
+    /// let toc_file = CdrdaoToc::from_disc(&disc);
+    /// println!("{}", toc_file.write("data.wav"));
+    /// ```
+    ///
+    /// [`Features::MCN`]: ../struct.Features.html#associatedconstant.MCN
+    /// [`Features::ISRC`]: ../struct.Features.html#associatedconstant.ISRC
+    #[cfg(feature = "libdiscid")]
+    pub fn from_disc(disc: &DiscId) -> CdrdaoToc {
+        CdrdaoToc::from_disc_info(&DiscInfo::from_disc(disc))
+    }
+
+    /// Writes the TOC file.
+    ///
+    /// The data of all tracks is taken from consecutive parts of the single file
+    /// `audio_file`, which holds the audio data of the whole disc starting with the pregap of
+    /// the first track. This is the layout used by `cdrdao read-cd`.
+    ///
+    /// A track of unknown [`length`](./struct.CdrdaoTrack.html#structfield.length) is
+    /// written without length, so it extends to the end of the file. This is only meaningful
+    /// for the last track.
+    pub fn write(&self, audio_file: &str) -> String {
+        let mut result = String::new();
+        let all_audio = self.tracks.iter().all(CdrdaoTrack::is_audio);
+        result.push_str(if all_audio { "CD_DA\n" } else { "CD_ROM\n" });
+        if let Some(catalog) = &self.catalog {
+            result.push_str(&format!("\nCATALOG \"{}\"\n", catalog));
+        }
+
+        let mut position = 0;
+        for (i, track) in self.tracks.iter().enumerate() {
+            result.push_str(&format!("\n// Track {}\nTRACK {}\n", i + 1, track.mode));
+            if let Some(isrc) = &track.isrc {
+                result.push_str(&format!("ISRC \"{}\"\n", isrc));
+            }
+            let start = match position {
+                0 => "0".to_string(),
+                start => msf::format(start),
+            };
+            match track.length {
+                Some(length) => result.push_str(&format!(
+                    "FILE \"{}\" {} {}\n",
+                    audio_file,
+                    start,
+                    msf::format(length)
+                )),
+                None => result.push_str(&format!("FILE \"{}\" {}\n", audio_file, start)),
+            }
+            if track.pregap > 0 {
+                result.push_str(&format!("START {}\n", msf::format(track.pregap)));
+            }
+            position += track.length.unwrap_or_default();
+        }
+        result
+    }
+
+    /// The tracks of the first session, without a trailing data track.
+    fn audio_session_tracks(&self) -> &[CdrdaoTrack] {
+        match self.tracks.split_last() {
+            Some((last, rest)) if !last.is_audio() && !rest.is_empty() => rest,
+            _ => &self.tracks,
+        }
+    }
+}
+
+impl FromStr for CdrdaoToc {
+    type Err = DiscError;
+
+    fn from_str(toc_file: &str) -> Result<CdrdaoToc, DiscError> {
+        CdrdaoToc::parse(toc_file)
+    }
+}
+
+fn current_track(toc: &mut CdrdaoToc, line_num: usize) -> Result<&mut CdrdaoTrack, DiscError> {
+    toc.tracks
+        .last_mut()
+        .ok_or_else(|| DiscError::parse(line_num, "Statement is only allowed inside a TRACK"))
+}
+
+fn add_length(
+    track: &mut CdrdaoTrack,
+    length: Option<i32>,
+    line_num: usize,
+) -> Result<(), DiscError> {
+    track.length = match (track.length, length) {
+        (Some(total), Some(length)) => Some(
+            total
+                .checked_add(length)
+                .ok_or_else(|| DiscError::parse(line_num, "Track too long"))?,
+        ),
+        _ => None,
+    };
+    Ok(())
+}
+
+fn disc_too_long() -> DiscError {
+    DiscError::invalid_toc(TocErrorKind::DiscTooLong, "Disc too long")
+}
+
+/// Parses a time given as `MM:SS:FF` or as number of samples.
+fn parse_time(value: &str, line_num: usize) -> Result<i32, DiscError> {
+    let error = || DiscError::parse(line_num, &format!("Invalid time {:?}", value));
+    if value.contains(':') {
+        return msf::parse(value).ok_or_else(error);
+    }
+    let samples: u64 = value.parse().map_err(|_| error())?;
+    let sectors = samples / SAMPLES_PER_SECTOR;
+    if sectors * SAMPLES_PER_SECTOR != samples {
+        return Err(DiscError::parse(
+            line_num,
+            &format!("Time {:?} is not a multiple of a sector", value),
+        ));
+    }
+    i32::try_from(sectors).map_err(|_| error())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CdrdaoToc;
+    use crate::{DiscError, DiscInfo, Toc, TrackIsrc};
+
+    const TOC_FILE: &str = r#"CD_DA
+
+CD_TEXT {
+  LANGUAGE_MAP {
+    0 : EN
+  }
+  LANGUAGE 0 {
+    TITLE "Album"
+    PERFORMER "Artist"
+  }
+}
+
+CATALOG "0724385583222"
+
+// Track 1
+TRACK AUDIO
+NO COPY
+NO PRE_EMPHASIS
+TWO_CHANNEL_AUDIO
+ISRC "GBAYE9300106"
+FILE "data.wav" 0 04:10:01
+
+// Track 2
+TRACK AUDIO
+TWO_CHANNEL_AUDIO
+FILE "data.wav" 04:10:01 04:37:62
+
+// Track 3
+TRACK AUDIO
+FILE "data.wav" 08:47:63 04:24:19
+
+// Track 4
+TRACK AUDIO
+FILE "data.wav" 13:12:07 04:21:20
+
+// Track 5
+TRACK AUDIO
+FILE "data.wav" 17:33:27 04:39:49
+
+// Track 6
+TRACK AUDIO
+FILE "data.wav" 22:13:01 05:29:32
+
+// Track 7
+TRACK AUDIO
+FILE "data.wav" 27:42:33 04:59:20
+
+// Track 8
+TRACK AUDIO
+FILE "data.wav" 32:41:53 04:14:08
+
+// Track 9
+TRACK AUDIO
+FILE "data.wav" 36:55:61 03:36:24
+
+// Track 10
+TRACK AUDIO
+ISRC "GBAYE9300115"
+FILE "data.wav" 40:32:10 05:19:50
+"#;
+
+    #[test]
+    fn cdrdao_parse() {
+        let toc_file = CdrdaoToc::parse(TOC_FILE).expect("parsing failed");
+        assert_eq!(Some("0724385583222".to_string()), toc_file.catalog);
+        assert_eq!(10, toc_file.tracks.len());
+        let track = &toc_file.tracks[0];
+        assert!(track.is_audio());
+        assert!(CdrdaoToc::parse("TRACK audio\n").unwrap().tracks[0].is_audio());
+        assert_eq!(Some("GBAYE9300106".to_string()), track.isrc);
+        assert_eq!(0, track.pregap);
+        assert_eq!(Some(18751), track.length);
+    }
+
+    #[test]
+    fn cdrdao_disc_info() {
+        let info = CdrdaoToc::parse(TOC_FILE)
+            .and_then(|toc_file| toc_file.disc_info())
+            .expect("disc_info failed");
+        assert_eq!(
+            "1 10 206535 150 18901 39738 59557 79152 100126 124833 147278 166336 182560",
+            info.toc_string()
+        );
+        assert_eq!("Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-", info.id());
+        assert_eq!("0724385583222", info.mcn());
+        assert_eq!("GBAYE9300106", info.nth_track(1).isrc.as_str());
+        assert_eq!(TrackIsrc::NotPresent, info.nth_track(2).isrc);
+        assert_eq!("GBAYE9300115", info.nth_track(10).isrc.as_str());
+    }
+
+    #[test]
+    fn cdrdao_pregap_start_silence() {
+        let toc_file = r#"
+TRACK AUDIO
+PREGAP 00:02:00
+FILE "01.wav" 0 588000
+TRACK AUDIO
+SILENCE 00:01:00
+START
+FILE "02.wav" 0
+TRACK AUDIO
+AUDIOFILE "03.wav" 0 00:10:00
+START 00:00:10
+"#;
+        let error = CdrdaoToc::parse(toc_file).unwrap().toc().unwrap_err();
+        assert_eq!(DiscError::parse(0, "Unknown length of track 2"), error);
+
+        let toc_file = toc_file.replace("FILE \"02.wav\" 0\n", "FILE \"02.wav\" 0 00:20:00\n");
+        let toc = CdrdaoToc::parse(&toc_file).unwrap().toc().unwrap();
+        // The length of the first file is given in samples, 1000 sectors = 588000 samples.
+        assert_eq!("1 3 3625 300 1375 2885", toc.to_string());
+    }
+
+    #[test]
+    fn cdrdao_enhanced_cd() {
+        let toc_file = r#"CD_ROM
+TRACK AUDIO
+FILE "data.wav" 0 04:10:01
+TRACK AUDIO
+FILE "data.wav" 04:10:01 04:37:62
+TRACK MODE1
+PREGAP 02:32:00
+DATAFILE "data.bin"
+"#;
+        let toc = CdrdaoToc::parse(toc_file).unwrap().toc().unwrap();
+        assert_eq!("1 2 39738 150 18901", toc.to_string());
+    }
+
+    #[test]
+    fn cdrdao_file_byte_offset() {
+        let toc_file = "TRACK AUDIO\nAUDIOFILE \"x.wav\" #0 0 4:10:01\n";
+        let toc = CdrdaoToc::parse(toc_file).unwrap().toc().unwrap();
+        assert_eq!("1 1 18901 150", toc.to_string());
+    }
+
+    #[test]
+    fn cdrdao_write() {
+        let toc: Toc = "1 2 39838 250 19001".parse().unwrap();
+        let isrcs = vec![TrackIsrc::from_raw("GBAYE9300106"), TrackIsrc::NotPresent];
+        let info = DiscInfo::from_parts(toc, "0724385583222".to_string(), isrcs);
+        let toc_file = CdrdaoToc::from_disc_info(&info);
+        let expected = r#"CD_DA
+
+CATALOG "0724385583222"
+
+// Track 1
+TRACK AUDIO
+ISRC "GBAYE9300106"
+FILE "data.wav" 0 04:11:26
+START 00:01:25
+
+// Track 2
+TRACK AUDIO
+FILE "data.wav" 04:11:26 04:37:62
+"#;
+        assert_eq!(expected, toc_file.write("data.wav"));
+        let parsed = CdrdaoToc::parse(expected).unwrap();
+        assert_eq!(toc_file, parsed);
+        assert_eq!(info, parsed.disc_info().unwrap());
+    }
+
+    #[test]
+    fn cdrdao_write_first_track_in_lead_in() {
+        let toc: Toc = "1 2 18851 100 9000".parse().unwrap();
+        let info = DiscInfo::from_parts(toc, String::new(), Vec::new());
+        let toc_file = CdrdaoToc::from_disc_info(&info);
+        assert_eq!(0, toc_file.tracks[0].pregap);
+        assert_eq!(Some(8900), toc_file.tracks[0].length);
+    }
+
+    #[test]
+    fn cdrdao_roundtrip() {
+        let toc_file = CdrdaoToc::parse(TOC_FILE).unwrap();
+        let written = toc_file.write("data.wav");
+        assert_eq!(toc_file, CdrdaoToc::parse(&written).unwrap());
+    }
+
+    #[test]
+    fn cdrdao_parse_errors() {
+        let error = |toc_file: &str| match CdrdaoToc::parse(toc_file) {
+            Err(DiscError::Parse { line, message }) => (line, message),
+            result => panic!("unexpected result {:?}", result),
+        };
+        assert_eq!(
+            (1, "Statement is only allowed inside a TRACK".to_string()),
+            error("FILE \"a.wav\" 0 1000")
+        );
+        assert_eq!((1, "Invalid CATALOG".to_string()), error("CATALOG \"123\""));
+        assert_eq!(
+            (2, "Invalid ISRC".to_string()),
+            error("TRACK AUDIO\nISRC \"ABC\"")
+        );
+        assert_eq!(
+            (2, "Invalid time \"00:61:00\"".to_string()),
+            error("TRACK AUDIO\nFILE \"a.wav\" 0 00:61:00")
+        );
+        assert_eq!(
+            (2, "Time \"1000\" is not a multiple of a sector".to_string()),
+            error("TRACK AUDIO\nFILE \"a.wav\" 0 1000")
+        );
+        assert_eq!(
+            (2, "Invalid time \"99999999:00:00\"".to_string()),
+            error("TRACK AUDIO\nFILE \"a.wav\" 0 99999999:00:00")
+        );
+        assert_eq!(
+            (2, "Invalid time \"1262720385024\"".to_string()),
+            error("TRACK AUDIO\nFILE \"a.wav\" 0 1262720385024")
+        );
+        assert_eq!(
+            (3, "Track too long".to_string()),
+            error("TRACK AUDIO\nSILENCE 400000:00:00\nSILENCE 400000:00:00")
+        );
+        assert_eq!(
+            (0, "TOC file contains no tracks".to_string()),
+            error("CD_DA\n// no tracks")
+        );
+    }
+}
@@ -0,0 +1,337 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Support for the [CUETools Database](http://cue.tools/wiki/CUETools_Database) (CTDB).
+//!
+//! The CTDB identifies a disc by its TOC ID, see [`toc_id`]. A rip is verified by comparing
+//! a CRC32 over the audio data of the whole disc, see [`disc_crc`], with the CRCs of the
+//! entries stored for the disc. [`CtdbResponse`] parses the XML returned by a CTDB lookup.
+//!
+//! [`toc_id`]: ./fn.toc_id.html
+//! [`disc_crc`]: ./fn.disc_crc.html
+//! [`CtdbResponse`]: ./struct.CtdbResponse.html
+
+use crate::native;
+use crate::xml;
+use crate::{DiscError, Toc};
+use sha1_smol::Sha1;
+use std::io::{self, Read};
+use std::str::FromStr;
+
+/// Number of bytes per sector of 16 bit stereo PCM data.
+const BYTES_PER_SECTOR: usize = 2352;
+
+/// Number of sectors at the start and the end of the disc, which are not part of the CRC.
+const SKIPPED_SECTORS: usize = 10;
+
+/// Number of bytes read at once while calculating the CRC.
+const BUFFER_SIZE: usize = 64 * BYTES_PER_SECTOR;
+
+/// Lookup table for the CRC32 (IEEE 802.3) polynomial in reversed form.
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+/// Calculates the CTDB TOC ID.
+///
+/// The ID is the SHA-1 hash over the offsets of all tracks but the first and the lead-out,
+/// relative to the offset of the first track and formatted as 8 digit uppercase hexadecimal
+/// numbers, padded with zeros to 100 entries. The hash is encoded with the same Base64
+/// variant as the MusicBrainz disc ID.
+///
+/// As the offsets are relative to the first track, the ID does not depend on the lead-in or
+/// on the number of the first track. For an enhanced CD only the audio session is used,
+/// which is what libdiscid reports.
+///
+/// # Examples
+///
+/// ```
+/// use discid::ctdb;
+/// use discid::Toc;
+///
+/// let toc: Toc = "1 2 39738 150 18901".parse().expect("parsing TOC failed");
+/// assert_eq!("9naNsaiOkMCudc1OXlqAIofFSeQ-", ctdb::toc_id(&toc));
+/// ```
+pub fn toc_id(toc: &Toc) -> String {
+    let first = toc.offsets()[0];
+    let mut sha = Sha1::new();
+    for &offset in toc.offsets()[1..].iter().chain(&[toc.lead_out()]) {
+        sha.update(format!("{:08X}", offset - first).as_bytes());
+    }
+    for _ in toc.track_count()..100 {
+        sha.update(b"00000000");
+    }
+    native::base64(&sha.digest().bytes())
+}
+
+/// Calculates the CTDB CRC32 over the audio data of a disc.
+///
+/// `reader` must provide the audio data of the whole disc as 16 bit little-endian stereo PCM,
+/// starting at the offset of the first track, as it is stored in a single file disc image.
+/// `sectors` is the length of the audio data in sectors, i.e. the lead-out minus the offset
+/// of the first track. The first and the last 10 sectors are not part of the CRC.
+///
+/// Fails with `ErrorKind::UnexpectedEof` if the reader provides less data than required.
+///
+/// # Examples
+///
+/// ```
+/// use discid::ctdb::disc_crc;
+///
+/// let silence = vec![0u8; 2352 * 30];
+/// let crc = disc_crc(&silence[..], 30).expect("reading failed");
+/// assert_eq!(0xea9d_1fe5, crc);
+/// ```
+pub fn disc_crc<R: Read>(mut reader: R, sectors: i32) -> io::Result<u32> {
+    let sectors = sectors.max(0) as usize;
+    let skipped = SKIPPED_SECTORS.min(sectors / 2) * BYTES_PER_SECTOR;
+    skip(&mut reader, skipped)?;
+
+    let mut crc = !0u32;
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut remaining = sectors * BYTES_PER_SECTOR - 2 * skipped;
+    while remaining > 0 {
+        let data = &mut buffer[..remaining.min(BUFFER_SIZE)];
+        reader.read_exact(data)?;
+        crc = crc32_update(crc, data);
+        remaining -= data.len();
+    }
+    skip(&mut reader, skipped)?;
+    Ok(!crc)
+}
+
+/// Skips `bytes` bytes of `reader`, failing if less data is available.
+fn skip<R: Read>(reader: &mut R, bytes: usize) -> io::Result<()> {
+    let skipped = io::copy(&mut reader.take(bytes as u64), &mut io::sink())?;
+    if skipped < bytes as u64 {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+    }
+    Ok(())
+}
+
+/// The response of a CTDB lookup.
+///
+/// # Examples
+///
+/// ```
+/// use discid::ctdb::CtdbResponse;
+///
+/// let response = CtdbResponse::parse(r#"<?xml version="1.0" encoding="UTF-8"?>
+/// <ctdb xmlns="http://db.cuetools.net/ns/mmd-1.0#">
+///   <entry confidence="12" crc32="3ad7c1e2" id="4711" npar="8" stride="5880"
+///          toc="0:18751:39588" trackcrcs="1e0a8f22 c4d21b6e" />
+/// </ctdb>"#).expect("parsing CTDB response failed");
+///
+/// let entry = response.find_crc(0x3ad7c1e2).expect("no matching entry");
+/// assert_eq!(12, entry.confidence);
+/// assert_eq!(vec![0x1e0a8f22, 0xc4d21b6e], entry.track_crcs);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CtdbResponse {
+    /// All entries stored for the disc.
+    pub entries: Vec<CtdbEntry>,
+}
+
+/// An entry of a [`CtdbResponse`].
+///
+/// [`CtdbResponse`]: ./struct.CtdbResponse.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CtdbEntry {
+    /// The ID of the entry in the database.
+    pub id: u32,
+
+    /// The number of submissions for this entry.
+    pub confidence: u32,
+
+    /// The CRC32 of the whole disc, see [`disc_crc`].
+    ///
+    /// [`disc_crc`]: ./fn.disc_crc.html
+    pub crc32: u32,
+
+    /// The CRC32 of each track, if provided.
+    pub track_crcs: Vec<u32>,
+
+    /// The TOC of the disc as stored in the database, a list of sector offsets separated by
+    /// colons.
+    pub toc: String,
+}
+
+impl CtdbResponse {
+    /// Parses the XML response of a CTDB lookup.
+    ///
+    /// All `entry` elements of the response are parsed, other elements like the metadata are
+    /// ignored.
+    pub fn parse(xml: &str) -> Result<CtdbResponse, DiscError> {
+        let root = xml::parse(xml)?;
+        if root.name != "ctdb" {
+            return Err(DiscError::parse(0, "Not a CTDB response"));
+        }
+        let entries = root
+            .children("entry")
+            .map(|element| {
+                Ok(CtdbEntry {
+                    id: attribute(element, "id", |value| value.parse().ok())?,
+                    confidence: attribute(element, "confidence", |value| value.parse().ok())?,
+                    crc32: attribute(element, "crc32", parse_hex)?,
+                    track_crcs: element
+                        .attr("trackcrcs")
+                        .unwrap_or_default()
+                        .split_whitespace()
+                        .map(|crc| parse_hex(crc).ok_or_else(|| invalid_attribute("trackcrcs")))
+                        .collect::<Result<_, _>>()?,
+                    toc: element.attr("toc").unwrap_or_default().to_string(),
+                })
+            })
+            .collect::<Result<_, DiscError>>()?;
+        Ok(CtdbResponse { entries })
+    }
+
+    /// Returns the entry matching the CRC32 of a rip, as calculated by [`disc_crc`].
+    ///
+    /// [`disc_crc`]: ./fn.disc_crc.html
+    pub fn find_crc(&self, crc32: u32) -> Option<&CtdbEntry> {
+        self.entries.iter().find(|entry| entry.crc32 == crc32)
+    }
+}
+
+impl FromStr for CtdbResponse {
+    type Err = DiscError;
+
+    fn from_str(xml: &str) -> Result<CtdbResponse, DiscError> {
+        CtdbResponse::parse(xml)
+    }
+}
+
+fn attribute<T>(
+    element: &xml::Element,
+    name: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Result<T, DiscError> {
+    element
+        .attr(name)
+        .and_then(parse)
+        .ok_or_else(|| invalid_attribute(name))
+}
+
+fn invalid_attribute(name: &str) -> DiscError {
+    DiscError::parse(
+        0,
+        &format!("Missing or invalid attribute {} in entry", name),
+    )
+}
+
+fn parse_hex(value: &str) -> Option<u32> {
+    u32::from_str_radix(value, 16).ok()
+}
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, &byte| {
+        CRC32_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc32_update, disc_crc, toc_id, CtdbResponse};
+    use crate::{DiscError, Toc};
+    use std::io::ErrorKind;
+
+    #[test]
+    fn ctdb_toc_id() {
+        let toc: Toc = "1 10 206535 150 18901 39738 59557 79152 100126 124833 147278 166336 182560"
+            .parse()
+            .unwrap();
+        assert_eq!("wNtOJIRTKI8yG5x_0oS8LkYIAto-", toc_id(&toc));
+
+        // The ID only depends on the offsets relative to the first track.
+        let toc = Toc::new(3, 4, 39838, vec![250, 19001]).unwrap();
+        assert_eq!("9naNsaiOkMCudc1OXlqAIofFSeQ-", toc_id(&toc));
+    }
+
+    #[test]
+    fn ctdb_crc32() {
+        assert_eq!(0xcbf4_3926, !crc32_update(!0, b"123456789"));
+    }
+
+    #[test]
+    fn ctdb_disc_crc() {
+        let data: Vec<u8> = (0..2352 * 30).map(|i| (i % 256) as u8).collect();
+        assert_eq!(0x8bcc_d5be, disc_crc(&data[..], 30).unwrap());
+        assert_eq!(
+            ErrorKind::UnexpectedEof,
+            disc_crc(&data[..], 31).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn ctdb_response_parse() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ctdb xmlns="http://db.cuetools.net/ns/mmd-1.0#" xmlns:ext="http://musicbrainz.org/ns/ext#-2.0">
+  <entry confidence="33" crc32="ba7f6ad9" hasparity="/parity/1234" id="1234" npar="8" stride="5880" toc="0:18751:206385" trackcrcs="0a1b2c3d 4e5f6a7b" />
+  <entry confidence="2" crc32="0000abcd" id="5678" npar="8" stride="5880" toc="0:18751:206385" />
+  <metadata source="musicbrainz" id="x" artist="Artist" album="Album" />
+</ctdb>"#;
+        let response: CtdbResponse = xml.parse().unwrap();
+        assert_eq!(2, response.entries.len());
+        let entry = &response.entries[0];
+        assert_eq!(1234, entry.id);
+        assert_eq!(33, entry.confidence);
+        assert_eq!(0xba7f_6ad9, entry.crc32);
+        assert_eq!(vec![0x0a1b_2c3d, 0x4e5f_6a7b], entry.track_crcs);
+        assert_eq!("0:18751:206385", entry.toc);
+        assert!(response.entries[1].track_crcs.is_empty());
+
+        assert_eq!(Some(5678), response.find_crc(0xabcd).map(|entry| entry.id));
+        assert_eq!(None, response.find_crc(0x1234));
+    }
+
+    #[test]
+    fn ctdb_response_parse_errors() {
+        assert_eq!(
+            Err(DiscError::parse(0, "Not a CTDB response")),
+            CtdbResponse::parse("<metadata/>")
+        );
+        assert_eq!(
+            Err(DiscError::parse(
+                0,
+                "Missing or invalid attribute crc32 in entry"
+            )),
+            CtdbResponse::parse(r#"<ctdb><entry id="1" confidence="1" crc32="xyz"/></ctdb>"#)
+        );
+        assert!(CtdbResponse::parse("<ctdb>").is_err());
+        assert!(CtdbResponse::parse("<ctdb></ctdb>")
+            .unwrap()
+            .entries
+            .is_empty());
+    }
+}
@@ -0,0 +1,909 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Support for CUE sheets.
+//!
+//! A CUE sheet describes the layout of a disc image, e.g. a BIN/CUE or a WAV/CUE rip. Use
+//! [`CueSheet::parse`] to read a CUE sheet and [`CueSheet::toc`] or [`CueSheet::disc_info`]
+//! to calculate the disc ID. [`CueSheet::from_disc_info`] creates a CUE sheet for a disc,
+//! which can be written using the `Display` implementation.
+//!
+//! CUE sheets store the track positions relative to the audio files, but not the length of
+//! the last file. Hence the length of each audio file in sectors must be provided to build
+//! the TOC. For raw audio files the length can be calculated from the file size with
+//! [`sectors_from_bytes`].
+//!
+//! The resulting [`Toc`] can be passed to [`DiscId::from_toc`], or its
+//! [`offsets_with_lead_out`] to [`DiscId::put`], if a `DiscId` is required.
+//!
+//! # Examples
+//!
+//! ```
+//! use discid::cue::CueSheet;
+//!
+//! let sheet = CueSheet::parse(r#"
+//! CATALOG 0724385583222
+//! FILE "image.bin" BINARY
+//!   TRACK 01 AUDIO
+//!     ISRC GBAYE9300106
+//!     INDEX 01 00:00:00
+//!   TRACK 02 AUDIO
+//!     INDEX 00 04:08:50
+//!     INDEX 01 04:10:01
+//! "#).expect("parsing CUE sheet failed");
+//!
+//! let info = sheet.disc_info(&[36789]).expect("invalid CUE sheet");
+//! assert_eq!("1 2 36939 150 18901", info.toc_string());
+//! assert_eq!("0724385583222", info.mcn());
+//! assert_eq!("GBAYE9300106", info.nth_track(1).isrc.as_str());
+//! ```
+//!
+//! [`CueSheet::parse`]: ./struct.CueSheet.html#method.parse
+//! [`CueSheet::toc`]: ./struct.CueSheet.html#method.toc
+//! [`CueSheet::disc_info`]: ./struct.CueSheet.html#method.disc_info
+//! [`CueSheet::from_disc_info`]: ./struct.CueSheet.html#method.from_disc_info
+//! [`sectors_from_bytes`]: ./fn.sectors_from_bytes.html
+//! [`Toc`]: ../struct.Toc.html
+//! [`offsets_with_lead_out`]: ../struct.Toc.html#method.offsets_with_lead_out
+//! [`DiscId::from_toc`]: ../struct.DiscId.html#method.from_toc
+//! [`DiscId::put`]: ../struct.DiscId.html#method.put
+
+use crate::isrc;
+use crate::msf;
+use crate::toc::{DATA_TRACK_GAP, LEAD_IN};
+#[cfg(feature = "libdiscid")]
+use crate::DiscId;
+use crate::{DiscError, DiscInfo, Isrc, Toc, TocErrorKind};
+use std::fmt;
+use std::str::FromStr;
+
+/// Number of bytes per sector of raw CD audio (16 bit stereo PCM).
+pub const BYTES_PER_SECTOR: u64 = 2352;
+
+/// A parsed CUE sheet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CueSheet {
+    /// The media catalogue number given by the `CATALOG` command, if present.
+    pub catalog: Option<String>,
+
+    /// The names of all files given by `FILE` commands, in order.
+    pub files: Vec<String>,
+
+    /// All tracks, in order.
+    pub tracks: Vec<CueTrack>,
+}
+
+/// A single track of a [`CueSheet`].
+///
+/// [`CueSheet`]: ./struct.CueSheet.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CueTrack {
+    /// Track number (1-99) of the track.
+    pub number: i32,
+
+    /// The data type of the track, e.g. `AUDIO` or `MODE1/2352`.
+    pub data_type: String,
+
+    /// The ISRC given by the `ISRC` command, if present.
+    pub isrc: Option<String>,
+
+    /// Length in sectors of the pregap given by the `PREGAP` command.
+    ///
+    /// The pregap is not part of the audio file.
+    pub pregap: i32,
+
+    /// Length in sectors of the postgap given by the `POSTGAP` command.
+    ///
+    /// The postgap is not part of the audio file.
+    pub postgap: i32,
+
+    /// The indexes of the track.
+    pub indexes: Vec<CueIndex>,
+}
+
+/// An `INDEX` of a [`CueTrack`].
+///
+/// [`CueTrack`]: ./struct.CueTrack.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CueIndex {
+    /// The index number, e.g. 0 for the pregap and 1 for the start of the track.
+    pub number: i32,
+
+    /// The position of the file in [`CueSheet::files`] this index refers to.
+    ///
+    /// [`CueSheet::files`]: ./struct.CueSheet.html#structfield.files
+    pub file: usize,
+
+    /// The position in sectors relative to the start of the file.
+    pub position: i32,
+}
+
+/// The file layout of a generated CUE sheet.
+///
+/// See [`CueSheet::from_disc_info`].
+///
+/// [`CueSheet::from_disc_info`]: ./struct.CueSheet.html#method.from_disc_info
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CueLayout {
+    /// All tracks are stored in a single file with the given name.
+    SingleFile(String),
+
+    /// Each track is stored in a separate file. The vector must contain one file name
+    /// per track.
+    ///
+    /// Audio before the first track (hidden track one audio) is stored in the file of the
+    /// first track.
+    FilePerTrack(Vec<String>),
+}
+
+impl CueTrack {
+    /// Returns true if this is an audio track.
+    pub fn is_audio(&self) -> bool {
+        self.data_type.eq_ignore_ascii_case("AUDIO")
+    }
+
+    /// Returns the index with the given number, if present.
+    pub fn index(&self, number: i32) -> Option<&CueIndex> {
+        self.indexes.iter().find(|index| index.number == number)
+    }
+}
+
+impl CueSheet {
+    /// Parses a CUE sheet.
+    ///
+    /// The commands `FILE`, `TRACK`, `INDEX`, `PREGAP`, `POSTGAP`, `CATALOG` and `ISRC` are
+    /// evaluated, all other commands are ignored.
+    pub fn parse(cue: &str) -> Result<CueSheet, DiscError> {
+        let mut sheet = CueSheet {
+            catalog: None,
+            files: Vec::new(),
+            tracks: Vec::new(),
+        };
+
+        for (i, line) in cue.lines().enumerate() {
+            let line_num = i + 1;
+            let tokens = tokenize(line.trim_start_matches('\u{feff}'));
+            let command = match tokens.first() {
+                Some(command) => command.to_uppercase(),
+                None => continue,
+            };
+            let argument = |n: usize| {
+                tokens.get(n).map(String::as_str).ok_or_else(|| {
+                    DiscError::parse(line_num, &format!("Missing argument for {}", command))
+                })
+            };
+
+            match command.as_str() {
+                "CATALOG" => {
+                    let catalog = argument(1)?;
+                    if catalog.len() != 13 || !catalog.bytes().all(|b| b.is_ascii_digit()) {
+                        return Err(DiscError::parse(line_num, "Invalid CATALOG"));
+                    }
+                    sheet.catalog = Some(catalog.to_string());
+                }
+                "FILE" => sheet.files.push(argument(1)?.to_string()),
+                "TRACK" => {
+                    if sheet.files.is_empty() {
+                        return Err(DiscError::parse(line_num, "TRACK before FILE"));
+                    }
+                    let number = parse_number(argument(1)?, line_num)?;
+                    let expected = sheet.tracks.last().map(|t| t.number + 1);
+                    if !(1..=99).contains(&number) || expected.is_some_and(|e| e != number) {
+                        return Err(DiscError::parse(line_num, "Invalid track number"));
+                    }
+                    sheet.tracks.push(CueTrack {
+                        number,
+                        data_type: argument(2)?.to_string(),
+                        isrc: None,
+                        pregap: 0,
+                        postgap: 0,
+                        indexes: Vec::new(),
+                    });
+                }
+                "INDEX" => {
+                    let number = parse_number(argument(1)?, line_num)?;
+                    let position = parse_time(argument(2)?, line_num)?;
+                    let file = sheet
+                        .files
+                        .len()
+                        .checked_sub(1)
+                        .ok_or_else(|| DiscError::parse(line_num, "INDEX before FILE"))?;
+                    current_track(&mut sheet, line_num)?.indexes.push(CueIndex {
+                        number,
+                        file,
+                        position,
+                    });
+                }
+                "PREGAP" => {
+                    let pregap = parse_time(argument(1)?, line_num)?;
+                    current_track(&mut sheet, line_num)?.pregap = pregap;
+                }
+                "POSTGAP" => {
+                    let postgap = parse_time(argument(1)?, line_num)?;
+                    current_track(&mut sheet, line_num)?.postgap = postgap;
+                }
+                "ISRC" => {
+                    let isrc = argument(1)?;
+                    if isrc.len() != 12 || !isrc.bytes().all(|b| b.is_ascii_alphanumeric()) {
+                        return Err(DiscError::parse(line_num, "Invalid ISRC"));
+                    }
+                    current_track(&mut sheet, line_num)?.isrc = Some(isrc.to_string());
+                }
+                _ => {}
+            }
+        }
+
+        if sheet.tracks.is_empty() {
+            return Err(DiscError::parse(0, "CUE sheet contains no tracks"));
+        }
+        Ok(sheet)
+    }
+
+    /// Creates a CUE sheet for the given disc.
+    ///
+    /// The `CATALOG` is set to the MCN and each track gets the ISRC, if they have been read
+    /// from the disc. The `INDEX 01` positions are derived from the track offsets without
+    /// the lead-in. If the first track does not start right after the lead-in, an
+    /// `INDEX 00` is added for the audio before the first track.
+    ///
+    /// Returns an error if `layout` is [`CueLayout::FilePerTrack`] and the number of file
+    /// names does not match the number of tracks.
+    ///
+    /// [`CueLayout::FilePerTrack`]: ./enum.CueLayout.html#variant.FilePerTrack
+    pub fn from_disc_info(info: &DiscInfo, layout: CueLayout) -> Result<CueSheet, DiscError> {
+        let tracks = info.tracks();
+        let (files, per_track) = match layout {
+            CueLayout::SingleFile(file) => (vec![file], false),
+            CueLayout::FilePerTrack(files) => {
+                if files.len() != tracks.len() {
+                    return Err(DiscError::Other(format!(
+                        "Expected {} file names, got {}",
+                        tracks.len(),
+                        files.len()
+                    )));
+                }
+                (files, true)
+            }
+        };
+
+        let mut cue_tracks = Vec::with_capacity(tracks.len());
+        for (i, track) in tracks.iter().enumerate() {
+            let (file, file_start) = if per_track && i > 0 {
+                (i, track.offset)
+            } else {
+                (0, LEAD_IN)
+            };
+            let mut indexes = Vec::with_capacity(2);
+            if i == 0 && track.offset > LEAD_IN {
+                indexes.push(CueIndex {
+                    number: 0,
+                    file,
+                    position: 0,
+                });
+            }
+            indexes.push(CueIndex {
+                number: 1,
+                file,
+                position: track.offset - file_start,
+            });
+            cue_tracks.push(CueTrack {
+                number: track.number,
+                data_type: "AUDIO".to_string(),
+                isrc: track.isrc.isrc().map(Isrc::to_string),
+                pregap: 0,
+                postgap: 0,
+                indexes,
+            });
+        }
+
+        Ok(CueSheet {
+            catalog: Some(info.mcn().to_string()).filter(|mcn| !mcn.is_empty()),
+            files,
+            tracks: cue_tracks,
+        })
+    }
+
+    /// Creates a CUE sheet for a disc read with libdiscid.
+    ///
+    /// Read the disc with [`Features::MCN`] and [`Features::ISRC`] to include the MCN and
+    /// ISRCs. See [`from_disc_info`](#method.from_disc_info) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use discid::cue::{CueLayout, CueSheet};
+    /// use discid::{DiscId, Features};
+    ///
+    /// let disc = DiscId::read_features(None, Features::all()).expect("Reading disc failed");
+    /// let sheet = CueSheet::from_disc(&disc, CueLayout::SingleFile("image.wav".to_string()))
+    ///     .expect("Creating CUE sheet failed");
+    /// println!("{}", sheet);
+    /// ```
+    ///
+    /// [`Features::MCN`]: ../struct.Features.html#associatedconstant.MCN
+    /// [`Features::ISRC`]: ../struct.Features.html#associatedconstant.ISRC
+    #[cfg(feature = "libdiscid")]
+    pub fn from_disc(disc: &DiscId, layout: CueLayout) -> Result<CueSheet, DiscError> {
+        CueSheet::from_disc_info(&DiscInfo::from_disc(disc), layout)
+    }
+
+    /// Calculates the TOC of the disc described by this CUE sheet.
+    ///
+    /// `file_sectors` must contain the length in sectors of each file listed in
+    /// [`files`](#structfield.files), in the same order. For a single file image this is
+    /// the total length of the audio.
+    ///
+    /// The 150 sectors lead-in are added to all offsets. If the last track is a data track,
+    /// as it is the case for enhanced CDs, it is not part of the TOC and the lead-out is
+    /// placed 11400 sectors before the data track.
+    pub fn toc(&self, file_sectors: &[i32]) -> Result<Toc, DiscError> {
+        if file_sectors.len() != self.files.len() {
+            return Err(DiscError::Other(format!(
+                "Expected length of {} files, got {}",
+                self.files.len(),
+                file_sectors.len()
+            )));
+        }
+
+        let mut file_starts = Vec::with_capacity(file_sectors.len());
+        let mut position = LEAD_IN;
+        for (i, &sectors) in file_sectors.iter().enumerate() {
+            if sectors < 0 {
+                return Err(DiscError::Other(format!(
+                    "Invalid length {} of file {}",
+                    sectors,
+                    i + 1
+                )));
+            }
+            file_starts.push(position);
+            position = position.checked_add(sectors).ok_or_else(disc_too_long)?;
+        }
+
+        let mut gaps: i32 = 0;
+        let mut offsets = Vec::with_capacity(self.tracks.len());
+        for track in self.tracks.iter() {
+            gaps = gaps.checked_add(track.pregap).ok_or_else(disc_too_long)?;
+            let index = track.index(1).ok_or_else(|| {
+                DiscError::parse(0, &format!("Track {} has no INDEX 01", track.number))
+            })?;
+            let file_start = file_starts.get(index.file).ok_or_else(|| {
+                DiscError::Other(format!("Track {} refers to unknown file", track.number))
+            })?;
+            let offset = file_start
+                .checked_add(index.position)
+                .and_then(|offset| offset.checked_add(gaps))
+                .ok_or_else(disc_too_long)?;
+            offsets.push(offset);
+            gaps = gaps.checked_add(track.postgap).ok_or_else(disc_too_long)?;
+        }
+        let mut lead_out = position.checked_add(gaps).ok_or_else(disc_too_long)?;
+
+        let tracks = self.audio_session_tracks();
+        if tracks.len() < self.tracks.len() {
+            lead_out = offsets[tracks.len()]
+                .checked_sub(DATA_TRACK_GAP)
+                .ok_or_else(|| DiscError::parse(0, "Invalid data track start"))?;
+            offsets.truncate(tracks.len());
+        }
+
+        let first_track = match self.tracks.first() {
+            Some(track) => track.number,
+            None => return Err(DiscError::parse(0, "CUE sheet contains no tracks")),
+        };
+        Toc::new(
+            first_track,
+            first_track + offsets.len() as i32 - 1,
+            lead_out,
+            offsets,
+        )
+    }
+
+    /// Returns a [`DiscInfo`] for the disc described by this CUE sheet.
+    ///
+    /// In addition to the TOC, as calculated by [`toc`](#method.toc), the `DiscInfo`
+    /// contains the MCN and ISRCs given in the CUE sheet.
+    ///
+    /// [`DiscInfo`]: ../struct.DiscInfo.html
+    pub fn disc_info(&self, file_sectors: &[i32]) -> Result<DiscInfo, DiscError> {
+        let toc = self.toc(file_sectors)?;
+        let isrcs = self
+            .audio_session_tracks()
+            .iter()
+            .map(|track| track.isrc.clone())
+            .collect();
+        let mcn = self.catalog.clone().unwrap_or_default();
+        Ok(DiscInfo::from_parts(toc, mcn, isrc::track_isrcs(isrcs)))
+    }
+
+    /// The tracks of the first session, without a trailing data track.
+    fn audio_session_tracks(&self) -> &[CueTrack] {
+        match self.tracks.split_last() {
+            Some((last, rest)) if !last.is_audio() && !rest.is_empty() => rest,
+            _ => &self.tracks,
+        }
+    }
+}
+
+impl FromStr for CueSheet {
+    type Err = DiscError;
+
+    fn from_str(cue: &str) -> Result<CueSheet, DiscError> {
+        CueSheet::parse(cue)
+    }
+}
+
+impl fmt::Display for CueSheet {
+    /// Writes the CUE sheet.
+    ///
+    /// Files with the extension `.bin`, `.img` or `.raw` are written with the file type
+    /// `BINARY`, all other files as `WAVE`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(catalog) = &self.catalog {
+            writeln!(f, "CATALOG {}", catalog)?;
+        }
+
+        let mut current_file = None;
+        for track in self.tracks.iter() {
+            let mut indexes = track.indexes.iter().peekable();
+            if let Some(index) = indexes.peek() {
+                write_file(f, self, index.file, &mut current_file)?;
+            }
+            writeln!(f, "  TRACK {:02} {}", track.number, track.data_type)?;
+            if let Some(isrc) = &track.isrc {
+                writeln!(f, "    ISRC {}", isrc)?;
+            }
+            if track.pregap > 0 {
+                writeln!(f, "    PREGAP {}", msf::format(track.pregap))?;
+            }
+            for index in indexes {
+                write_file(f, self, index.file, &mut current_file)?;
+                writeln!(
+                    f,
+                    "    INDEX {:02} {}",
+                    index.number,
+                    msf::format(index.position)
+                )?;
+            }
+            if track.postgap > 0 {
+                writeln!(f, "    POSTGAP {}", msf::format(track.postgap))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_file(
+    f: &mut fmt::Formatter,
+    sheet: &CueSheet,
+    file: usize,
+    current_file: &mut Option<usize>,
+) -> fmt::Result {
+    if *current_file == Some(file) {
+        return Ok(());
+    }
+    *current_file = Some(file);
+    let name = sheet
+        .files
+        .get(file)
+        .map(String::as_str)
+        .unwrap_or_default();
+    let extension = name.rsplit('.').next().unwrap_or_default().to_lowercase();
+    let file_type = match extension.as_str() {
+        "bin" | "img" | "raw" => "BINARY",
+        _ => "WAVE",
+    };
+    writeln!(f, "FILE \"{}\" {}", name, file_type)
+}
+
+/// Calculates the number of sectors of raw CD audio with the given size in bytes.
+///
+/// `bytes` must be the size of the raw audio data, e.g. the size of a BIN file or the size
+/// of the data chunk of a WAV file. A partial sector at the end is counted as a full sector.
+pub fn sectors_from_bytes(bytes: u64) -> i32 {
+    bytes.div_ceil(BYTES_PER_SECTOR) as i32
+}
+
+fn disc_too_long() -> DiscError {
+    DiscError::invalid_toc(TocErrorKind::DiscTooLong, "Disc too long")
+}
+
+fn current_track(sheet: &mut CueSheet, line_num: usize) -> Result<&mut CueTrack, DiscError> {
+    sheet
+        .tracks
+        .last_mut()
+        .ok_or_else(|| DiscError::parse(line_num, "Command is only allowed inside a TRACK"))
+}
+
+fn parse_number(value: &str, line_num: usize) -> Result<i32, DiscError> {
+    value
+        .parse()
+        .map_err(|_| DiscError::parse(line_num, &format!("Invalid number {:?}", value)))
+}
+
+fn parse_time(value: &str, line_num: usize) -> Result<i32, DiscError> {
+    msf::parse(value)
+        .ok_or_else(|| DiscError::parse(line_num, &format!("Invalid time {:?}", value)))
+}
+
+/// Splits a line into whitespace separated tokens. Double quoted tokens may contain spaces.
+pub(crate) fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            tokens.push(chars.by_ref().take_while(|&c| c != '"').collect());
+        } else {
+            let mut token = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+            tokens.push(token);
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sectors_from_bytes, tokenize, CueLayout, CueSheet};
+    use crate::{DiscError, DiscInfo, Toc, TocErrorKind, TrackIsrc};
+
+    const SINGLE_FILE: &str = r#"REM GENRE Rock
+REM DATE 1993
+PERFORMER "Artist"
+TITLE "Album"
+CATALOG 0724385583222
+FILE "Artist - Album.wav" WAVE
+  TRACK 01 AUDIO
+    TITLE "Track 1"
+    ISRC GBAYE9300106
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 00 04:08:50
+    INDEX 01 04:10:01
+  TRACK 03 AUDIO
+    INDEX 01 08:47:63
+  TRACK 04 AUDIO
+    INDEX 01 13:12:07
+  TRACK 05 AUDIO
+    INDEX 01 17:33:27
+  TRACK 06 AUDIO
+    INDEX 01 22:13:01
+  TRACK 07 AUDIO
+    INDEX 01 27:42:33
+  TRACK 08 AUDIO
+    INDEX 01 32:41:53
+  TRACK 09 AUDIO
+    INDEX 01 36:55:61
+  TRACK 10 AUDIO
+    ISRC GBAYE9300115
+    INDEX 01 40:32:10
+"#;
+
+    #[test]
+    fn cue_parse() {
+        let sheet = CueSheet::parse(SINGLE_FILE).expect("parsing failed");
+        assert_eq!(Some("0724385583222".to_string()), sheet.catalog);
+        assert_eq!(vec!["Artist - Album.wav".to_string()], sheet.files);
+        assert_eq!(10, sheet.tracks.len());
+        let track = &sheet.tracks[1];
+        assert_eq!(2, track.number);
+        assert!(track.is_audio());
+        assert_eq!(None, track.isrc);
+        assert_eq!(2, track.indexes.len());
+        assert_eq!(18650, track.index(0).unwrap().position);
+        assert_eq!(18751, track.index(1).unwrap().position);
+    }
+
+    #[test]
+    fn cue_single_file_toc() {
+        let sheet = CueSheet::parse(SINGLE_FILE).expect("parsing failed");
+        let toc = sheet.toc(&[206385]).expect("toc failed");
+        assert_eq!("Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-", toc.id());
+    }
+
+    #[test]
+    fn cue_single_file_disc_info() {
+        let sheet = CueSheet::parse(SINGLE_FILE).expect("parsing failed");
+        let info = sheet
+            .disc_info(&[sectors_from_bytes(206385 * 2352)])
+            .expect("disc_info failed");
+        assert_eq!("Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-", info.id());
+        assert_eq!("0724385583222", info.mcn());
+        assert_eq!("GBAYE9300106", info.nth_track(1).isrc.as_str());
+        assert_eq!(TrackIsrc::NotPresent, info.nth_track(2).isrc);
+        assert_eq!("GBAYE9300115", info.nth_track(10).isrc.as_str());
+    }
+
+    #[test]
+    fn cue_file_per_track() {
+        let cue = r#"
+FILE "01.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+FILE "02.wav" WAVE
+  TRACK 02 AUDIO
+    INDEX 00 00:00:00
+    INDEX 01 00:01:00
+FILE "03.wav" WAVE
+  TRACK 03 AUDIO
+    INDEX 01 00:00:00
+"#;
+        let sheet = CueSheet::parse(cue).expect("parsing failed");
+        let toc = sheet.toc(&[1000, 2000, 3000]).expect("toc failed");
+        assert_eq!("1 3 6150 150 1225 3150", toc.to_string());
+    }
+
+    #[test]
+    fn cue_gaps_appended_to_previous_file() {
+        // Gaps are stored at the end of the previous track's file, as written by EAC.
+        let cue = r#"
+FILE "01.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 00 00:10:00
+FILE "02.wav" WAVE
+    INDEX 01 00:00:00
+"#;
+        let sheet = CueSheet::parse(cue).expect("parsing failed");
+        let toc = sheet.toc(&[1000, 2000]).expect("toc failed");
+        assert_eq!("1 2 3150 150 1150", toc.to_string());
+    }
+
+    #[test]
+    fn cue_pregap_postgap() {
+        let cue = r#"
+FILE "image.bin" BINARY
+  TRACK 01 AUDIO
+    PREGAP 00:01:00
+    INDEX 01 00:00:00
+    POSTGAP 00:00:10
+  TRACK 02 AUDIO
+    INDEX 01 00:10:00
+"#;
+        let sheet = CueSheet::parse(cue).expect("parsing failed");
+        let toc = sheet.toc(&[2000]).expect("toc failed");
+        assert_eq!("1 2 2235 225 985", toc.to_string());
+    }
+
+    #[test]
+    fn cue_enhanced_cd() {
+        let cue = r#"
+FILE "image.bin" BINARY
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 01 00:10:00
+  TRACK 03 MODE1/2352
+    INDEX 01 10:00:00
+"#;
+        let sheet = CueSheet::parse(cue).expect("parsing failed");
+        let toc = sheet.toc(&[50000]).expect("toc failed");
+        assert_eq!(2, toc.last_track());
+        assert_eq!(45000 + 150 - 11400, toc.lead_out());
+        assert_eq!(2, sheet.disc_info(&[50000]).unwrap().tracks().len());
+    }
+
+    #[test]
+    fn cue_first_track_not_one() {
+        let cue = "FILE \"a.bin\" BINARY\nTRACK 03 AUDIO\nINDEX 01 00:00:00\nTRACK 04 AUDIO\nINDEX 01 00:10:00\n";
+        let toc = CueSheet::parse(cue).unwrap().toc(&[2000]).unwrap();
+        assert_eq!("3 4 2150 150 900", toc.to_string());
+    }
+
+    #[test]
+    fn cue_parse_errors() {
+        let error = |cue: &str| match CueSheet::parse(cue) {
+            Err(DiscError::Parse { line, message }) => (line, message),
+            result => panic!("unexpected result {:?}", result),
+        };
+        assert_eq!(
+            (1, "TRACK before FILE".to_string()),
+            error("TRACK 01 AUDIO")
+        );
+        assert_eq!(
+            (3, "Invalid time \"00:61:00\"".to_string()),
+            error("FILE a.bin BINARY\nTRACK 01 AUDIO\nINDEX 01 00:61:00")
+        );
+        assert_eq!(
+            (3, "Invalid time \"99999999:00:00\"".to_string()),
+            error("FILE a.bin BINARY\nTRACK 01 AUDIO\nINDEX 01 99999999:00:00")
+        );
+        assert_eq!(
+            (1, "INDEX before FILE".to_string()),
+            error("INDEX 01 00:00:00")
+        );
+        assert_eq!(
+            (3, "Invalid track number".to_string()),
+            error("FILE a.bin BINARY\nTRACK 01 AUDIO\nTRACK 03 AUDIO")
+        );
+        assert_eq!((1, "Invalid CATALOG".to_string()), error("CATALOG 123"));
+        assert_eq!(
+            (3, "Invalid ISRC".to_string()),
+            error("FILE a.bin BINARY\nTRACK 01 AUDIO\nISRC ABC")
+        );
+        assert_eq!(
+            (2, "Missing argument for INDEX".to_string()),
+            error("FILE a.bin BINARY\nINDEX 01")
+        );
+        assert_eq!(
+            (0, "CUE sheet contains no tracks".to_string()),
+            error("FILE a.bin BINARY")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Track 2 has no INDEX 01")]
+    fn cue_missing_index_01() {
+        let cue = "FILE a.bin BINARY\nTRACK 01 AUDIO\nINDEX 01 00:00:00\nTRACK 02 AUDIO\n";
+        CueSheet::parse(cue).unwrap().toc(&[2000]).unwrap();
+    }
+
+    #[test]
+    fn cue_disc_too_long() {
+        let sheet = CueSheet::parse(SINGLE_FILE).unwrap();
+        match sheet.toc(&[i32::MAX]) {
+            Err(DiscError::InvalidToc { kind, .. }) => assert_eq!(TocErrorKind::DiscTooLong, kind),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn cue_invalid_toc_input() {
+        let cue = r#"
+FILE "audio.bin" BINARY
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+FILE "data.bin" BINARY
+  TRACK 02 MODE1/2352
+    INDEX 01 00:00:00
+"#;
+        let sheet = CueSheet::parse(cue).unwrap();
+        assert_eq!(
+            Err(DiscError::Other(
+                "Invalid length -2147483000 of file 1".to_string()
+            )),
+            sheet.toc(&[-2147483000, 5])
+        );
+
+        let mut sheet = CueSheet::parse(SINGLE_FILE).unwrap();
+        sheet.tracks[1].indexes[1].file = 1;
+        assert_eq!(
+            Err(DiscError::Other(
+                "Track 2 refers to unknown file".to_string()
+            )),
+            sheet.toc(&[206385])
+        );
+        sheet.tracks.clear();
+        assert_eq!(
+            Err(DiscError::parse(0, "CUE sheet contains no tracks")),
+            sheet.toc(&[206385])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected length of 1 files, got 2")]
+    fn cue_file_count_mismatch() {
+        let sheet = CueSheet::parse(SINGLE_FILE).unwrap();
+        sheet.toc(&[1000, 2000]).unwrap();
+    }
+
+    #[test]
+    fn cue_sectors_from_bytes() {
+        assert_eq!(0, sectors_from_bytes(0));
+        assert_eq!(1, sectors_from_bytes(1));
+        assert_eq!(1, sectors_from_bytes(2352));
+        assert_eq!(2, sectors_from_bytes(2353));
+    }
+
+    #[test]
+    fn cue_tokenize() {
+        assert_eq!(
+            vec!["FILE", "My File.wav", "WAVE"],
+            tokenize("  FILE \"My File.wav\" WAVE")
+        );
+        assert_eq!(
+            vec!["INDEX", "01", "00:00:00"],
+            tokenize("INDEX 01 00:00:00\r")
+        );
+        assert!(tokenize("   ").is_empty());
+    }
+
+    fn test_disc_info() -> DiscInfo {
+        let toc: Toc = "1 3 6150 300 1225 3150".parse().unwrap();
+        let isrcs = vec![
+            TrackIsrc::from_raw("GBAYE9300106"),
+            TrackIsrc::NotPresent,
+            TrackIsrc::NotPresent,
+        ];
+        DiscInfo::from_parts(toc, "0724385583222".to_string(), isrcs)
+    }
+
+    #[test]
+    fn cue_write_single_file() {
+        let info = test_disc_info();
+        let sheet = CueSheet::from_disc_info(&info, CueLayout::SingleFile("disc.bin".into()))
+            .expect("from_disc_info failed");
+        let expected = "CATALOG 0724385583222
+FILE \"disc.bin\" BINARY
+  TRACK 01 AUDIO
+    ISRC GBAYE9300106
+    INDEX 00 00:00:00
+    INDEX 01 00:02:00
+  TRACK 02 AUDIO
+    INDEX 01 00:14:25
+  TRACK 03 AUDIO
+    INDEX 01 00:40:00
+";
+        assert_eq!(expected, sheet.to_string());
+        let parsed = CueSheet::parse(expected).unwrap();
+        assert_eq!(sheet, parsed);
+        assert_eq!(info, parsed.disc_info(&[6000]).unwrap());
+    }
+
+    #[test]
+    fn cue_write_file_per_track() {
+        let info = test_disc_info();
+        let files = vec!["01.wav".into(), "02.wav".into(), "03.wav".into()];
+        let sheet = CueSheet::from_disc_info(&info, CueLayout::FilePerTrack(files))
+            .expect("from_disc_info failed");
+        let expected = "CATALOG 0724385583222
+FILE \"01.wav\" WAVE
+  TRACK 01 AUDIO
+    ISRC GBAYE9300106
+    INDEX 00 00:00:00
+    INDEX 01 00:02:00
+FILE \"02.wav\" WAVE
+  TRACK 02 AUDIO
+    INDEX 01 00:00:00
+FILE \"03.wav\" WAVE
+  TRACK 03 AUDIO
+    INDEX 01 00:00:00
+";
+        assert_eq!(expected, sheet.to_string());
+        let parsed = CueSheet::parse(expected).unwrap();
+        assert_eq!(info, parsed.disc_info(&[1075, 1925, 3000]).unwrap());
+    }
+
+    #[test]
+    fn cue_write_roundtrip() {
+        let sheet = CueSheet::parse(SINGLE_FILE).unwrap();
+        let written = sheet.to_string();
+        assert!(written.starts_with("CATALOG 0724385583222\nFILE \"Artist - Album.wav\" WAVE\n"));
+        assert_eq!(sheet, CueSheet::parse(&written).unwrap());
+
+        let cue = "FILE \"01.wav\" WAVE\n  TRACK 01 AUDIO\n    PREGAP 00:01:00\n    INDEX 01 00:00:00\n  TRACK 02 AUDIO\n    INDEX 00 00:10:00\nFILE \"02.wav\" WAVE\n    INDEX 01 00:00:00\n    POSTGAP 00:00:10\n";
+        let sheet = CueSheet::parse(cue).unwrap();
+        assert_eq!(cue, sheet.to_string());
+    }
+
+    #[test]
+    fn cue_write_file_count_mismatch() {
+        let info = test_disc_info();
+        let layout = CueLayout::FilePerTrack(vec!["01.wav".into()]);
+        assert_eq!(
+            Err(DiscError::Other("Expected 3 file names, got 1".to_string())),
+            CueSheet::from_disc_info(&info, layout)
+        );
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Support for Exact Audio Copy (EAC) log files.
+//!
+//! EAC logs contain a table with the TOC of the extracted CD, listing the start and end
+//! sector of each track:
+//!
+//! ```text
+//! TOC of the extracted CD
+//!
+//!      Track |   Start  |  Length  | Start sector | End sector
+//!     ---------------------------------------------------------
+//!         1  |  0:00.00 |  4:10.01 |         0    |    18750
+//!         2  |  4:10.01 |  4:37.62 |     18751    |    39587
+//! ```
+//!
+//! [`parse_toc`] reads this table and returns the [`Toc`] of the disc. Only the table rows
+//! are evaluated, so logs written by localized versions of EAC are supported as well.
+//!
+//! # Examples
+//!
+//! ```
+//! use discid::eac;
+//!
+//! let log = "
+//! TOC of the extracted CD
+//!
+//!      Track |   Start  |  Length  | Start sector | End sector
+//!     ---------------------------------------------------------
+//!         1  |  0:00.00 |  4:10.01 |         0    |    18750
+//!         2  |  4:10.01 |  4:37.62 |     18751    |    39587
+//! ";
+//!
+//! let toc = eac::parse_toc(log).expect("parsing EAC log failed");
+//! assert_eq!("1 2 39738 150 18901", toc.to_string());
+//! ```
+//!
+//! [`parse_toc`]: ./fn.parse_toc.html
+//! [`Toc`]: ../struct.Toc.html
+
+use crate::log;
+use crate::{DiscError, Toc};
+
+/// Extracts the TOC from an EAC log.
+///
+/// The first TOC table found in the log is used. If the last track starts after a gap of
+/// at least 11400 sectors, it is considered to be the data track of an enhanced CD. The
+/// data track is not part of the TOC and the lead-out is placed 11400 sectors before it,
+/// the same way libdiscid handles enhanced CDs.
+///
+/// The log must already be decoded, see [`decode`](./fn.decode.html).
+pub fn parse_toc(log: &str) -> Result<Toc, DiscError> {
+    log::toc_from_rows(&log::parse_toc_table(log)?)
+}
+
+/// Decodes the raw content of an EAC log file.
+///
+/// EAC writes logs in UTF-16 with a byte order mark. Files starting with a UTF-16 byte order
+/// mark are decoded accordingly, all other files are expected to be UTF-8.
+pub fn decode(bytes: &[u8]) -> Result<String, DiscError> {
+    let utf16 = |to_u16: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|pair| to_u16([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16(&units).map_err(|_| DiscError::parse(0, "Invalid UTF-16 data"))
+    };
+    match bytes {
+        [0xff, 0xfe, ..] => utf16(u16::from_le_bytes),
+        [0xfe, 0xff, ..] => utf16(u16::from_be_bytes),
+        _ => {
+            let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+            String::from_utf8(bytes.to_vec()).map_err(|_| DiscError::parse(0, "Invalid UTF-8 data"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, parse_toc};
+    use crate::DiscError;
+
+    const LOG: &str = "Exact Audio Copy V1.0 beta 3 from 29. August 2011
+
+EAC extraction logfile from 16. May 2020, 12:00
+
+Artist / Album
+
+Used drive  : PLEXTOR DVDR   PX-716A   Adapter: 0  ID: 0
+
+TOC of the extracted CD
+
+     Track |   Start  |  Length  | Start sector | End sector
+    ---------------------------------------------------------
+        1  |  0:00.00 |  4:10.01 |         0    |    18750
+        2  |  4:10.01 |  4:37.62 |     18751    |    39587
+        3  |  8:47.63 |  4:24.19 |     39588    |    59406
+        4  | 13:12.07 |  4:21.20 |     59407    |    79001
+        5  | 17:33.27 |  4:39.49 |     79002    |    99975
+        6  | 22:13.01 |  5:29.32 |     99976    |   124682
+        7  | 27:42.33 |  4:59.20 |    124683    |   147127
+        8  | 32:41.53 |  4:14.08 |    147128    |   166185
+        9  | 36:55.61 |  3:36.24 |    166186    |   182409
+       10  | 40:32.10 |  5:19.60 |    182410    |   206384
+
+
+Range status and errors
+
+Selected range
+
+     Filename C:\\Music\\Artist - Album.wav
+";
+
+    #[test]
+    fn eac_parse_toc() {
+        let toc = parse_toc(LOG).expect("parsing failed");
+        assert_eq!(
+            "1 10 206535 150 18901 39738 59557 79152 100126 124833 147278 166336 182560",
+            toc.to_string()
+        );
+        assert_eq!("Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-", toc.id());
+        assert_eq!("830abf0a", toc.freedb_id());
+    }
+
+    #[test]
+    fn eac_parse_toc_localized() {
+        let log = "Exact Audio Copy V1.0 beta 3 vom 29. August 2011
+
+Inhaltsverzeichnis der extrahierten CD
+
+     Titel  |  Start   |  Länge   | Startsektor | Endsektor
+    ---------------------------------------------------------
+        1   |  0:00.00 |  4:10.01 |       0     |   18750
+        2   |  4:10.01 |  4:37.62 |   18751     |   39587
+
+Bereichsstatus und Fehler
+";
+        let toc = parse_toc(log).expect("parsing failed");
+        assert_eq!("1 2 39738 150 18901", toc.to_string());
+    }
+
+    #[test]
+    fn eac_parse_toc_enhanced_cd() {
+        let log = "
+     Track |   Start  |  Length  | Start sector | End sector
+    ---------------------------------------------------------
+        1  |  0:00.00 |  4:10.01 |         0    |    18750
+        2  |  4:10.01 |  4:37.62 |     18751    |    39587
+        3  | 11:24.38 |  2:00.00 |     50988    |    59987
+";
+        let toc = parse_toc(log).expect("parsing failed");
+        assert_eq!("1 2 39738 150 18901", toc.to_string());
+    }
+
+    #[test]
+    fn eac_parse_toc_first_table_only() {
+        let log = format!("{}\n{}", LOG, LOG.replace("206384", "206000"));
+        assert_eq!(206535, parse_toc(&log).unwrap().lead_out());
+    }
+
+    #[test]
+    fn eac_parse_toc_errors() {
+        assert_eq!(
+            Err(DiscError::parse(0, "No TOC found in log")),
+            parse_toc("Exact Audio Copy V1.0 beta 3")
+        );
+        assert_eq!(
+            Err(DiscError::parse(2, "Invalid sector \"x\"")),
+            parse_toc("  1 | 0:00.00 | 4:10.01 | 0 | 18750\n  2 | 4:10.01 | 4:37.62 | x | 39587")
+        );
+        assert_eq!(
+            Err(DiscError::parse(1, "Invalid sector \"2147483647\"")),
+            parse_toc("  1 | 0:00.00 | 4:10.01 | 0 | 2147483647")
+        );
+        assert_eq!(
+            Err(DiscError::parse(1, "Invalid sector \"-150\"")),
+            parse_toc("  1 | 0:00.00 | 4:10.01 | -150 | 18750")
+        );
+        assert_eq!(
+            Err(DiscError::parse(2, "Invalid track number")),
+            parse_toc(
+                "  1 | 0:00.00 | 4:10.01 | 0 | 18750\n  3 | 4:10.01 | 4:37.62 | 18751 | 39587"
+            )
+        );
+        assert_eq!(
+            Err(DiscError::parse(1, "End sector before start sector")),
+            parse_toc("  1 | 0:00.00 | 4:10.01 | 100 | 50")
+        );
+    }
+
+    #[test]
+    fn eac_decode() {
+        let text = "TOC of the extracted CD";
+        let mut utf16le = vec![0xff, 0xfe];
+        utf16le.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        assert_eq!(Ok(text.to_string()), decode(&utf16le));
+
+        let mut utf16be = vec![0xfe, 0xff];
+        utf16be.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+        assert_eq!(Ok(text.to_string()), decode(&utf16be));
+
+        assert_eq!(Ok(text.to_string()), decode(text.as_bytes()));
+        assert_eq!(
+            Ok(text.to_string()),
+            decode(b"\xef\xbb\xbfTOC of the extracted CD")
+        );
+        assert_eq!(
+            Err(DiscError::parse(0, "Invalid UTF-8 data")),
+            decode(b"\xff\x00")
+        );
+    }
+}
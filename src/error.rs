@@ -0,0 +1,296 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::error::Error;
+use std::fmt;
+use std::num::ParseIntError;
+
+/// This is returned on errors reading the disc or setting the TOC.
+///
+/// Each variant keeps the original error message, e.g. as reported by libdiscid, which is
+/// also used for the `Display` implementation.
+///
+/// # Examples
+///
+/// ```
+/// use discid::{DiscError, Toc, TocErrorKind};
+///
+/// match "1 2 242457 150".parse::<Toc>() {
+///     Err(DiscError::InvalidToc { kind, .. }) => assert_eq!(
+///         TocErrorKind::TrackCountMismatch {
+///             offsets: 1,
+///             tracks: 2
+///         },
+///         kind
+///     ),
+///     _ => panic!("expected an invalid TOC error"),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiscError {
+    /// The disc drive could not be opened.
+    DeviceOpen(String),
+
+    /// There is no disc in the drive or the disc could not be read.
+    NoMedium(String),
+
+    /// The requested operation is not supported on this platform.
+    UnsupportedFeature(String),
+
+    /// The TOC is not valid.
+    InvalidToc {
+        /// The reason why the TOC is invalid.
+        kind: TocErrorKind,
+        /// The original error message.
+        message: String,
+    },
+
+    /// An integer could not be parsed.
+    ParseInt {
+        /// The zero based position of the value in the parsed input.
+        position: usize,
+        /// The underlying parse error.
+        source: ParseIntError,
+    },
+
+    /// A file or text could not be parsed.
+    Parse {
+        /// The one based line number where the error occurred, or 0 if the error does not
+        /// relate to a specific line.
+        line: usize,
+        /// A description of the error.
+        message: String,
+    },
+
+    /// Any other error.
+    Other(String),
+}
+
+/// The reason why a TOC is invalid.
+///
+/// See [`DiscError::InvalidToc`].
+///
+/// [`DiscError::InvalidToc`]: ./enum.DiscError.html#variant.InvalidToc
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TocErrorKind {
+    /// The first or last track number is outside the range 1 to 99, or the first track number
+    /// is larger than the last.
+    IllegalTrackLimits,
+
+    /// There are more offsets than tracks or more than 100 offsets in total.
+    TooManyOffsets,
+
+    /// The number of offsets does not match the number of tracks.
+    TrackCountMismatch {
+        /// The number of given track offsets.
+        offsets: i32,
+        /// The number of tracks according to the first and last track number.
+        tracks: i32,
+    },
+
+    /// The track offsets are not in ascending order.
+    NonMonotonicOffsets,
+
+    /// A track offset is negative or lies beyond the lead-out.
+    InvalidOffset,
+
+    /// The lead-out exceeds the maximum disc length.
+    DiscTooLong,
+
+    /// The TOC could not be parsed, e.g. because it has not enough elements.
+    Malformed,
+}
+
+/// A disc ID given in an external source does not match the ID calculated from the TOC.
+///
+/// This indicates that either the TOC or the given disc ID have been modified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscIdMismatch {
+    /// The disc ID as given in the external source, e.g. a log file.
+    pub expected: String,
+    /// The disc ID calculated from the TOC.
+    pub calculated: String,
+}
+
+impl DiscError {
+    pub(crate) fn invalid_toc(kind: TocErrorKind, message: &str) -> Self {
+        DiscError::InvalidToc {
+            kind,
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn parse_int(position: usize, source: ParseIntError) -> Self {
+        DiscError::ParseInt { position, source }
+    }
+
+    pub(crate) fn parse(line: usize, message: &str) -> Self {
+        DiscError::Parse {
+            line,
+            message: message.to_string(),
+        }
+    }
+
+    /// Creates an error from an error message reported by libdiscid.
+    ///
+    /// libdiscid only provides error messages, hence the kind of error is derived from
+    /// the message text.
+    #[cfg(feature = "libdiscid")]
+    pub(crate) fn from_libdiscid(message: &str) -> Self {
+        let message = message.to_string();
+        let lower = message.to_lowercase();
+        if lower.contains("cannot open") {
+            DiscError::DeviceOpen(message)
+        } else if lower.contains("no medium")
+            || lower.contains("no disc")
+            || lower.contains("medium not present")
+            || lower.contains("cannot read table of contents")
+        {
+            DiscError::NoMedium(message)
+        } else if lower.contains("not implemented") || lower.contains("not supported") {
+            DiscError::UnsupportedFeature(message)
+        } else if lower.contains("illegal track limits") {
+            DiscError::invalid_toc(TocErrorKind::IllegalTrackLimits, &message)
+        } else if lower.contains("invalid offset") {
+            DiscError::invalid_toc(TocErrorKind::InvalidOffset, &message)
+        } else if lower.contains("disc too long") {
+            DiscError::invalid_toc(TocErrorKind::DiscTooLong, &message)
+        } else {
+            DiscError::Other(message)
+        }
+    }
+}
+
+impl Error for DiscError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DiscError::ParseInt { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DiscError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiscError::DeviceOpen(message)
+            | DiscError::NoMedium(message)
+            | DiscError::UnsupportedFeature(message)
+            | DiscError::InvalidToc { message, .. }
+            | DiscError::Other(message) => write!(f, "DiscError: {}", message),
+            DiscError::ParseInt { position, source } => {
+                write!(f, "DiscError: {} at position {}", source, position)
+            }
+            DiscError::Parse { line: 0, message } => write!(f, "DiscError: {}", message),
+            DiscError::Parse { line, message } => {
+                write!(f, "DiscError: {} in line {}", message, line)
+            }
+        }
+    }
+}
+
+impl Error for DiscIdMismatch {}
+
+impl fmt::Display for DiscIdMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Disc ID mismatch: expected {}, calculated {}",
+            self.expected, self.calculated
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DiscError, DiscIdMismatch, TocErrorKind};
+    use std::error::Error;
+
+    #[test]
+    fn disc_error_fmt() {
+        let error = DiscError::Other("The message".to_string());
+        assert_eq!("DiscError: The message", format!("{}", error));
+    }
+
+    #[test]
+    fn disc_error_fmt_invalid_toc() {
+        let error = DiscError::invalid_toc(TocErrorKind::InvalidOffset, "Invalid offset");
+        assert_eq!("DiscError: Invalid offset", format!("{}", error));
+    }
+
+    #[test]
+    fn disc_error_fmt_parse_int() {
+        let source = "a".parse::<i32>().unwrap_err();
+        let error = DiscError::parse_int(4, source);
+        assert_eq!(
+            "DiscError: invalid digit found in string at position 4",
+            format!("{}", error)
+        );
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn disc_error_fmt_parse() {
+        let error = DiscError::parse(3, "Invalid time");
+        assert_eq!("DiscError: Invalid time in line 3", format!("{}", error));
+        let error = DiscError::parse(0, "No tracks found");
+        assert_eq!("DiscError: No tracks found", format!("{}", error));
+    }
+
+    #[test]
+    fn disc_id_mismatch_fmt() {
+        let mismatch = DiscIdMismatch {
+            expected: "Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-".to_string(),
+            calculated: "lSOVc5h6IXSuzcamJS1Gp4_tRuA-".to_string(),
+        };
+        assert_eq!(
+            "Disc ID mismatch: expected Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-, calculated lSOVc5h6IXSuzcamJS1Gp4_tRuA-",
+            format!("{}", mismatch)
+        );
+    }
+
+    #[test]
+    fn disc_error_debug() {
+        let error = DiscError::Other("The message".to_string());
+        assert_eq!("Other(\"The message\")", format!("{:?}", error));
+    }
+
+    #[test]
+    #[cfg(feature = "libdiscid")]
+    fn disc_error_from_libdiscid() {
+        assert_eq!(
+            DiscError::DeviceOpen("cannot open device `/dev/sr1'".to_string()),
+            DiscError::from_libdiscid("cannot open device `/dev/sr1'")
+        );
+        assert_eq!(
+            DiscError::NoMedium("cannot read table of contents".to_string()),
+            DiscError::from_libdiscid("cannot read table of contents")
+        );
+        assert_eq!(
+            DiscError::UnsupportedFeature(
+                "discid_read() not implemented on this platform".to_string()
+            ),
+            DiscError::from_libdiscid("discid_read() not implemented on this platform")
+        );
+        assert_eq!(
+            DiscError::invalid_toc(TocErrorKind::IllegalTrackLimits, "Illegal track limits"),
+            DiscError::from_libdiscid("Illegal track limits")
+        );
+        assert_eq!(
+            DiscError::Other("something else".to_string()),
+            DiscError::from_libdiscid("something else")
+        );
+    }
+}
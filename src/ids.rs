@@ -0,0 +1,251 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::native;
+use crate::{DiscError, Toc};
+use std::fmt;
+use std::str::FromStr;
+
+/// Length of a MusicBrainz disc ID, a Base64 encoded SHA-1 hash.
+const MUSICBRAINZ_ID_LENGTH: usize = 28;
+
+/// A validated MusicBrainz disc ID.
+///
+/// The ID consists of 28 characters of the alphabet `A-Z`, `a-z`, `0-9`, `.`, `_` and `-`
+/// and always ends with `-`.
+///
+/// # Examples
+///
+/// ```
+/// use discid::{MusicBrainzDiscId, Toc};
+///
+/// let id: MusicBrainzDiscId = "MS0ykA8iqhwhwey1JFwbNd4IIqE-".parse().expect("invalid disc ID");
+/// let toc: Toc = "1 2 39738 150 18901".parse().expect("parsing TOC failed");
+/// assert_eq!(id, MusicBrainzDiscId::from_toc(&toc));
+/// assert!("MS0ykA8iqhwhwey1JFwbNd4IIqE".parse::<MusicBrainzDiscId>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MusicBrainzDiscId(String);
+
+/// A validated FreeDB disc ID.
+///
+/// The ID is a 32 bit number, usually written as 8 hexadecimal digits. It consists of a
+/// checksum over the track start times in the highest byte, the total length of the disc in
+/// seconds in the middle two bytes and the number of tracks in the lowest byte.
+///
+/// # Examples
+///
+/// ```
+/// use discid::FreedbId;
+///
+/// let id: FreedbId = "830ABF0A".parse().expect("invalid FreeDB ID");
+/// assert_eq!("830abf0a", id.to_string());
+/// assert_eq!(0x83, id.checksum());
+/// assert_eq!(2751, id.seconds());
+/// assert_eq!(10, id.track_count());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FreedbId(u32);
+
+impl MusicBrainzDiscId {
+    /// Calculates the MusicBrainz disc ID for a TOC.
+    pub fn from_toc(toc: &Toc) -> MusicBrainzDiscId {
+        MusicBrainzDiscId(toc.id())
+    }
+
+    /// The disc ID as string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FreedbId {
+    /// Creates a FreeDB ID from its numeric value.
+    pub fn new(value: u32) -> FreedbId {
+        FreedbId(value)
+    }
+
+    /// Calculates the FreeDB ID for a TOC.
+    pub fn from_toc(toc: &Toc) -> FreedbId {
+        FreedbId(native::freedb_id_value(
+            toc.first_track(),
+            toc.last_track(),
+            &toc.full_offsets(),
+        ))
+    }
+
+    /// The numeric value of the ID.
+    pub fn value(self) -> u32 {
+        self.0
+    }
+
+    /// The checksum over the digit sums of the track start times in seconds, modulo 255.
+    pub fn checksum(self) -> u8 {
+        (self.0 >> 24) as u8
+    }
+
+    /// The length of the disc in seconds, from the start of the first track to the lead-out.
+    pub fn seconds(self) -> u16 {
+        (self.0 >> 8) as u16
+    }
+
+    /// The number of tracks.
+    pub fn track_count(self) -> u8 {
+        self.0 as u8
+    }
+}
+
+impl FromStr for MusicBrainzDiscId {
+    type Err = DiscError;
+
+    fn from_str(id: &str) -> Result<MusicBrainzDiscId, DiscError> {
+        let valid = id.len() == MUSICBRAINZ_ID_LENGTH
+            && id.ends_with('-')
+            && id
+                .bytes()
+                .all(|c| c.is_ascii_alphanumeric() || c == b'.' || c == b'_' || c == b'-');
+        if !valid {
+            return Err(DiscError::parse(0, "Invalid MusicBrainz disc ID"));
+        }
+        Ok(MusicBrainzDiscId(id.to_string()))
+    }
+}
+
+impl FromStr for FreedbId {
+    type Err = DiscError;
+
+    fn from_str(id: &str) -> Result<FreedbId, DiscError> {
+        if id.len() != 8 || !id.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(DiscError::parse(0, "Invalid FreeDB ID"));
+        }
+        u32::from_str_radix(id, 16)
+            .map(FreedbId)
+            .map_err(|_| DiscError::parse(0, "Invalid FreeDB ID"))
+    }
+}
+
+impl fmt::Display for MusicBrainzDiscId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Display for FreedbId {
+    /// Formats the ID as 8 lowercase hexadecimal digits, as returned by libdiscid.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:08x}", self.0)
+    }
+}
+
+impl AsRef<str> for MusicBrainzDiscId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<MusicBrainzDiscId> for String {
+    fn from(id: MusicBrainzDiscId) -> String {
+        id.0
+    }
+}
+
+impl From<FreedbId> for u32 {
+    fn from(id: FreedbId) -> u32 {
+        id.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FreedbId, MusicBrainzDiscId};
+    use crate::{DiscError, Toc};
+    use std::collections::HashSet;
+
+    fn toc() -> Toc {
+        "1 10 206535 150 18901 39738 59557 79152 100126 124833 147278 166336 182560"
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn ids_musicbrainz_disc_id() {
+        let id: MusicBrainzDiscId = "Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-".parse().unwrap();
+        assert_eq!(id, MusicBrainzDiscId::from_toc(&toc()));
+        assert_eq!("Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-", id.to_string());
+        assert_eq!("Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-", id.as_str());
+        assert_eq!(
+            "Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-".to_string(),
+            String::from(id.clone())
+        );
+
+        let other: MusicBrainzDiscId = "MS0ykA8iqhwhwey1JFwbNd4IIqE-".parse().unwrap();
+        assert!(other < id);
+        let set: HashSet<_> = vec![id.clone(), other, id].into_iter().collect();
+        assert_eq!(2, set.len());
+    }
+
+    #[test]
+    fn ids_musicbrainz_disc_id_invalid() {
+        for invalid in &[
+            "",
+            "Wn8eRBtfLDfM0qjYPdxrz.Zjs_U",
+            "Wn8eRBtfLDfM0qjYPdxrz.Zjs_U--",
+            "Wn8eRBtfLDfM0qjYPdxrz.Zjs_Ua",
+            "Wn8eRBtfLDfM0qjYPdxrz+Zjs_U-",
+            "Wn8eRBtfLDfM0qjYPdxrz.Zjs_ä-",
+        ] {
+            assert_eq!(
+                Err(DiscError::parse(0, "Invalid MusicBrainz disc ID")),
+                invalid.parse::<MusicBrainzDiscId>(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn ids_freedb_id() {
+        let id: FreedbId = "830abf0a".parse().unwrap();
+        assert_eq!(id, FreedbId::from_toc(&toc()));
+        assert_eq!(FreedbId::new(0x830a_bf0a), id);
+        assert_eq!(0x830a_bf0a, u32::from(id));
+        assert_eq!(0x830a_bf0a, id.value());
+        assert_eq!(toc().freedb_id(), id.to_string());
+        assert_eq!(0x83, id.checksum());
+        assert_eq!(0x0abf, id.seconds());
+        assert_eq!(10, id.track_count());
+        assert_eq!("0000000a", FreedbId::new(10).to_string());
+        assert!(FreedbId::new(10) < id);
+    }
+
+    #[test]
+    fn ids_freedb_id_invalid() {
+        for invalid in &[
+            "",
+            "830abf0",
+            "830abf0a0",
+            "830abfxa",
+            "+30abf0a",
+            " 830abf0",
+        ] {
+            assert_eq!(
+                Err(DiscError::parse(0, "Invalid FreeDB ID")),
+                invalid.parse::<FreedbId>(),
+                "{}",
+                invalid
+            );
+        }
+    }
+}
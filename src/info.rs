@@ -0,0 +1,443 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::accuraterip::AccurateRipId;
+#[cfg(feature = "libdiscid")]
+use crate::DiscId;
+use crate::{Toc, Track, TrackIsrc};
+use std::fmt;
+use std::sync::Arc;
+
+/// A detached snapshot of all information about a disc.
+///
+/// Unlike [`DiscId`], a `DiscInfo` does not keep a libdiscid handle. All values are read
+/// once on creation, so accessing them is cheap. Cloning a `DiscInfo` only increments a
+/// reference count. `DiscInfo` implements `Eq` and `Hash` and can be used as a map key.
+///
+/// # Examples
+///
+/// ```
+/// use discid::{DiscInfo, Toc};
+/// use std::collections::HashMap;
+///
+/// let toc: Toc = "1 2 2000 150 1000".parse().expect("parsing TOC failed");
+/// let info = DiscInfo::from_toc(toc);
+/// assert_eq!("YPv1TJ03f7pu6fgwp1cpr3cVsf0-", info.id());
+///
+/// let mut ratings = HashMap::new();
+/// ratings.insert(info.clone(), 5);
+/// assert_eq!(Some(&5), ratings.get(&info));
+/// ```
+///
+/// [`DiscId`]: ./struct.DiscId.html
+#[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "RawDiscInfo", into = "RawDiscInfo")
+)]
+pub struct DiscInfo {
+    inner: Arc<DiscInfoData>,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct DiscInfoData {
+    id: String,
+    freedb_id: String,
+    toc: Toc,
+    mcn: String,
+    submission_url: String,
+    tracks: Vec<Track>,
+}
+
+impl DiscInfo {
+    /// Creates a snapshot of the given [`DiscId`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use discid::{DiscId, DiscInfo};
+    ///
+    /// let disc = DiscId::parse("1 2 2000 150 1000").expect("DiscId::parse() failed");
+    /// let info = DiscInfo::from_disc(&disc);
+    /// assert_eq!(disc.id(), info.id());
+    /// assert_eq!(2, info.tracks().len());
+    /// ```
+    ///
+    /// [`DiscId`]: ./struct.DiscId.html
+    #[cfg(feature = "libdiscid")]
+    pub fn from_disc(disc: &DiscId) -> DiscInfo {
+        DiscInfo {
+            inner: Arc::new(DiscInfoData {
+                id: disc.id(),
+                freedb_id: disc.freedb_id(),
+                toc: disc.toc(),
+                mcn: disc.mcn(),
+                submission_url: disc.submission_url(),
+                tracks: disc.tracks().collect(),
+            }),
+        }
+    }
+
+    /// Creates a snapshot for the given [`Toc`] without using libdiscid.
+    ///
+    /// The disc IDs are calculated by the pure Rust implementation. The MCN will be empty and
+    /// all ISRCs [`TrackIsrc::NotRead`].
+    ///
+    /// [`Toc`]: ./struct.Toc.html
+    /// [`TrackIsrc::NotRead`]: ./enum.TrackIsrc.html#variant.NotRead
+    pub fn from_toc(toc: Toc) -> DiscInfo {
+        DiscInfo::from_parts(toc, String::new(), Vec::new())
+    }
+
+    /// Creates a snapshot for the given [`Toc`] with the given MCN and ISRCs.
+    ///
+    /// `isrcs` holds the ISRC for each track, starting with the first track. Missing ISRCs
+    /// are set to [`TrackIsrc::NotRead`].
+    pub(crate) fn from_parts(toc: Toc, mcn: String, mut isrcs: Vec<TrackIsrc>) -> DiscInfo {
+        isrcs.resize(toc.track_count() as usize, TrackIsrc::NotRead);
+        let tracks = (toc.first_track()..=toc.last_track())
+            .zip(isrcs)
+            .map(|(number, isrc)| Track {
+                number,
+                offset: toc.track_offset(number).unwrap_or_default(),
+                sectors: toc.track_sectors(number).unwrap_or_default(),
+                isrc,
+            })
+            .collect();
+        DiscInfo {
+            inner: Arc::new(DiscInfoData {
+                id: toc.id(),
+                freedb_id: toc.freedb_id(),
+                mcn,
+                submission_url: toc.submission_url(),
+                toc,
+                tracks,
+            }),
+        }
+    }
+
+    /// The MusicBrainz disc ID.
+    pub fn id(&self) -> &str {
+        &self.inner.id
+    }
+
+    /// The FreeDB disc ID.
+    pub fn freedb_id(&self) -> &str {
+        &self.inner.freedb_id
+    }
+
+    /// The AccurateRip disc ID.
+    ///
+    /// See [`AccurateRipId::from_toc`] for details.
+    ///
+    /// [`AccurateRipId::from_toc`]: ./accuraterip/struct.AccurateRipId.html#method.from_toc
+    pub fn accuraterip_id(&self) -> AccurateRipId {
+        self.inner.toc.accuraterip_id()
+    }
+
+    /// The CUETools Database TOC ID.
+    ///
+    /// See [`ctdb::toc_id`] for details.
+    ///
+    /// [`ctdb::toc_id`]: ./ctdb/fn.toc_id.html
+    pub fn ctdb_toc_id(&self) -> String {
+        self.inner.toc.ctdb_toc_id()
+    }
+
+    /// The table of contents of the disc.
+    pub fn toc(&self) -> &Toc {
+        &self.inner.toc
+    }
+
+    /// Return a string representing CD Table Of Contents (TOC).
+    ///
+    /// See [`DiscId::toc_string`] for details about the format.
+    ///
+    /// [`DiscId::toc_string`]: ./struct.DiscId.html#method.toc_string
+    pub fn toc_string(&self) -> String {
+        self.inner.toc.to_string()
+    }
+
+    /// An URL for submitting the DiscID to MusicBrainz.
+    pub fn submission_url(&self) -> &str {
+        &self.inner.submission_url
+    }
+
+    /// The number of the first track on this disc.
+    pub fn first_track_num(&self) -> i32 {
+        self.inner.toc.first_track()
+    }
+
+    /// The number of the last track on this disc.
+    pub fn last_track_num(&self) -> i32 {
+        self.inner.toc.last_track()
+    }
+
+    /// The length of the disc in sectors.
+    pub fn sectors(&self) -> i32 {
+        self.inner.toc.lead_out()
+    }
+
+    /// The media catalogue number on the disc, if present.
+    pub fn mcn(&self) -> &str {
+        &self.inner.mcn
+    }
+
+    /// All tracks on the disc, ordered by track number.
+    pub fn tracks(&self) -> &[Track] {
+        &self.inner.tracks
+    }
+
+    /// Returns the [`Track`] with the given number.
+    ///
+    /// # Panics
+    /// Panics if `number` is outside the range given by [`first_track_num`]
+    /// and [`last_track_num`].
+    ///
+    /// [`Track`]: ./struct.Track.html
+    /// [`first_track_num`]: #method.first_track_num
+    /// [`last_track_num`]: #method.last_track_num
+    pub fn nth_track(&self, number: i32) -> &Track {
+        let first = self.first_track_num();
+        let last = self.last_track_num();
+        if number < first || number > last {
+            panic!(
+                "track number out of bounds: given {}, expected between {} and {}",
+                number, first, last
+            );
+        }
+        &self.inner.tracks[(number - first) as usize]
+    }
+}
+
+impl fmt::Debug for DiscInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DiscInfo")
+            .field("id", &self.inner.id)
+            .field("freedb_id", &self.inner.freedb_id)
+            .field("toc", &self.inner.toc.to_string())
+            .field("mcn", &self.inner.mcn)
+            .field("tracks", &self.inner.tracks)
+            .finish()
+    }
+}
+
+/// Unvalidated representation of a [`DiscInfo`] used for serialization.
+///
+/// On deserialization the disc IDs, the submission URL and the track offsets are
+/// recalculated from the TOC. Values given in the serialized data must match.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RawDiscInfo {
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    freedb_id: String,
+    toc: Toc,
+    #[serde(default)]
+    mcn: String,
+    #[serde(default)]
+    submission_url: String,
+    #[serde(default)]
+    tracks: Vec<Track>,
+}
+
+#[cfg(feature = "serde")]
+impl From<DiscInfo> for RawDiscInfo {
+    fn from(info: DiscInfo) -> RawDiscInfo {
+        let data = &info.inner;
+        RawDiscInfo {
+            id: data.id.clone(),
+            freedb_id: data.freedb_id.clone(),
+            toc: data.toc.clone(),
+            mcn: data.mcn.clone(),
+            submission_url: data.submission_url.clone(),
+            tracks: data.tracks.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<RawDiscInfo> for DiscInfo {
+    type Error = crate::DiscError;
+
+    fn try_from(raw: RawDiscInfo) -> Result<DiscInfo, crate::DiscError> {
+        let isrcs = raw.tracks.iter().map(|track| track.isrc.clone()).collect();
+        let info = DiscInfo::from_parts(raw.toc, raw.mcn, isrcs);
+        if !raw.id.is_empty() && raw.id != info.id() {
+            return Err(crate::DiscError::Other(format!(
+                "Disc ID {} does not match TOC, expected {}",
+                raw.id,
+                info.id()
+            )));
+        }
+        if !raw.freedb_id.is_empty() && raw.freedb_id != info.freedb_id() {
+            return Err(crate::DiscError::Other(format!(
+                "FreeDB ID {} does not match TOC, expected {}",
+                raw.freedb_id,
+                info.freedb_id()
+            )));
+        }
+        if !raw.tracks.is_empty() {
+            let matches = raw.tracks.len() == info.tracks().len()
+                && raw.tracks.iter().zip(info.tracks()).all(|(raw, track)| {
+                    raw.number == track.number
+                        && raw.offset == track.offset
+                        && raw.sectors == track.sectors
+                });
+            if !matches {
+                return Err(crate::DiscError::Other(
+                    "Tracks do not match TOC".to_string(),
+                ));
+            }
+        }
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DiscInfo;
+    #[cfg(feature = "libdiscid")]
+    use crate::DiscId;
+    use crate::{Toc, TrackIsrc};
+    use std::collections::HashSet;
+
+    fn toc() -> Toc {
+        "1 10 206535 150 18901 39738 59557 79152 100126 124833 147278 166336 182560"
+            .parse()
+            .expect("parsing TOC failed")
+    }
+
+    #[test]
+    fn disc_info_from_toc() {
+        let info = DiscInfo::from_toc(toc());
+        assert_eq!("Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-", info.id());
+        assert_eq!("830abf0a", info.freedb_id());
+        assert_eq!(1, info.first_track_num());
+        assert_eq!(10, info.last_track_num());
+        assert_eq!(206535, info.sectors());
+        assert_eq!("", info.mcn());
+        assert_eq!(10, info.tracks().len());
+        let track = info.nth_track(4);
+        assert_eq!(4, track.number);
+        assert_eq!(59557, track.offset);
+        assert_eq!(79152 - 59557, track.sectors);
+        assert_eq!(TrackIsrc::NotRead, track.isrc);
+        assert_eq!(206535 - 182560, info.nth_track(10).sectors);
+    }
+
+    #[test]
+    #[should_panic(expected = "track number out of bounds: given 11, expected between 1 and 10")]
+    fn disc_info_nth_track_out_of_bounds() {
+        DiscInfo::from_toc(toc()).nth_track(11);
+    }
+
+    #[test]
+    fn disc_info_clone_eq_hash() {
+        let info = DiscInfo::from_toc(toc());
+        let other = DiscInfo::from_toc("1 2 2000 150 1000".parse().unwrap());
+        assert_eq!(info, info.clone());
+        assert_ne!(info, other);
+
+        let mut set = HashSet::new();
+        set.insert(info.clone());
+        set.insert(info.clone());
+        set.insert(other);
+        assert_eq!(2, set.len());
+        assert!(set.contains(&DiscInfo::from_toc(toc())));
+    }
+
+    #[test]
+    fn disc_info_debug() {
+        let info = DiscInfo::from_toc("1 1 2000 150".parse().unwrap());
+        assert_eq!(
+            "DiscInfo { id: \"8uB2IaJvZ8WofI2c0xZy.rKMzWs-\", freedb_id: \"02001801\", \
+             toc: \"1 1 2000 150\", mcn: \"\", tracks: [Track { number: 1, offset: 150, \
+             sectors: 1850, isrc: NotRead }] }",
+            format!("{:?}", info)
+        );
+    }
+
+    #[test]
+    fn disc_info_from_parts() {
+        let toc = "1 2 2000 150 1000".parse().unwrap();
+        let isrcs = vec![TrackIsrc::from_raw("DEE250800230")];
+        let info = DiscInfo::from_parts(toc, "4050538793819".to_string(), isrcs);
+        assert_eq!("4050538793819", info.mcn());
+        assert_eq!("DEE250800230", info.nth_track(1).isrc.as_str());
+        assert_eq!(TrackIsrc::NotRead, info.nth_track(2).isrc);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn disc_info_serde() {
+        let toc = "1 2 2000 150 1000".parse().unwrap();
+        let isrcs = vec![TrackIsrc::from_raw("DEE250800230"), TrackIsrc::NotPresent];
+        let info = DiscInfo::from_parts(toc, "4050538793819".to_string(), isrcs);
+        let json = serde_json::to_string(&info).expect("serializing failed");
+        let deserialized: DiscInfo = serde_json::from_str(&json).expect("deserializing failed");
+        assert_eq!(info, deserialized);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn disc_info_serde_recalculates_ids() {
+        let json = r#"{
+            "toc": {"first_track": 1, "last_track": 2, "lead_out": 2000, "offsets": [150, 1000]},
+            "mcn": "4050538793819"
+        }"#;
+        let info: DiscInfo = serde_json::from_str(json).expect("deserializing failed");
+        assert_eq!("YPv1TJ03f7pu6fgwp1cpr3cVsf0-", info.id());
+        assert_eq!("4050538793819", info.mcn());
+        assert_eq!(850, info.nth_track(2).offset - info.nth_track(1).offset);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn disc_info_serde_id_mismatch() {
+        let json = r#"{
+            "id": "Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-",
+            "toc": {"first_track": 1, "last_track": 2, "lead_out": 2000, "offsets": [150, 1000]}
+        }"#;
+        let error = serde_json::from_str::<DiscInfo>(json).unwrap_err();
+        assert!(error.to_string().contains("does not match TOC"));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn disc_info_serde_invalid_toc() {
+        let json = r#"{
+            "toc": {"first_track": 1, "last_track": 3, "lead_out": 2000, "offsets": [150, 1000]}
+        }"#;
+        let error = serde_json::from_str::<DiscInfo>(json).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Number of offsets 2 does not match track count 3"));
+    }
+
+    #[test]
+    #[cfg(feature = "libdiscid")]
+    fn disc_info_from_disc() {
+        let disc = DiscId::from_toc(&toc()).expect("DiscId::from_toc failed");
+        let info = DiscInfo::from_disc(&disc);
+        assert_eq!(disc.id(), info.id());
+        assert_eq!(disc.freedb_id(), info.freedb_id());
+        assert_eq!(disc.toc_string(), info.toc_string());
+        assert_eq!(disc.submission_url(), info.submission_url());
+        assert_eq!(DiscInfo::from_toc(toc()), info);
+    }
+}
@@ -0,0 +1,316 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::DiscError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Length of an ISRC without separators.
+const ISRC_LENGTH: usize = 12;
+
+/// A validated International Standard Recording Code (ISRC).
+///
+/// An ISRC consists of 12 characters: a two letter country code, a three character
+/// alphanumeric registrant code, two digits for the year of reference and a five digit
+/// designation code. Lowercase letters are accepted when parsing and converted to uppercase.
+///
+/// # Examples
+///
+/// ```
+/// use discid::Isrc;
+///
+/// let isrc: Isrc = "GBAYE9300106".parse().expect("invalid ISRC");
+/// assert_eq!("GB", isrc.country_code());
+/// assert_eq!("AYE", isrc.registrant());
+/// assert_eq!(93, isrc.year());
+/// assert_eq!(106, isrc.designation_code());
+/// assert!("GBAYE930010".parse::<Isrc>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Isrc(String);
+
+impl Isrc {
+    /// The ISRC as string, without separators.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The two letter country code, e.g. `GB`.
+    pub fn country_code(&self) -> &str {
+        &self.0[0..2]
+    }
+
+    /// The three character registrant code, identifying the issuer of the ISRC.
+    pub fn registrant(&self) -> &str {
+        &self.0[2..5]
+    }
+
+    /// The last two digits of the year of reference, usually the year the ISRC was assigned.
+    pub fn year(&self) -> u8 {
+        self.0[5..7].parse().unwrap_or_default()
+    }
+
+    /// The designation code, identifying the recording among those of the registrant and year.
+    pub fn designation_code(&self) -> u32 {
+        self.0[7..].parse().unwrap_or_default()
+    }
+}
+
+impl FromStr for Isrc {
+    type Err = DiscError;
+
+    fn from_str(isrc: &str) -> Result<Isrc, DiscError> {
+        let bytes = isrc.as_bytes();
+        let valid = bytes.len() == ISRC_LENGTH
+            && bytes[0..2].iter().all(u8::is_ascii_alphabetic)
+            && bytes[2..5].iter().all(u8::is_ascii_alphanumeric)
+            && bytes[5..].iter().all(u8::is_ascii_digit);
+        if !valid {
+            return Err(DiscError::parse(0, "Invalid ISRC"));
+        }
+        Ok(Isrc(isrc.to_ascii_uppercase()))
+    }
+}
+
+impl fmt::Display for Isrc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for Isrc {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The ISRC of a track, together with the reason if no ISRC is available.
+///
+/// Use [`TrackIsrc::from_raw`] to classify the ISRC as read from a disc.
+///
+/// # Examples
+///
+/// ```
+/// use discid::TrackIsrc;
+///
+/// let isrc = TrackIsrc::from_raw("GBAYE9300106");
+/// assert_eq!(Some("GB"), isrc.isrc().map(|isrc| isrc.country_code()));
+/// assert_eq!(TrackIsrc::NotPresent, TrackIsrc::from_raw("000000000000"));
+/// assert_eq!(TrackIsrc::Invalid("GB?YE9300106".to_string()), TrackIsrc::from_raw("GB?YE9300106"));
+/// ```
+///
+/// [`TrackIsrc::from_raw`]: ./enum.TrackIsrc.html#method.from_raw
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "Option<String>", into = "Option<String>")
+)]
+pub enum TrackIsrc {
+    /// ISRCs have not been read, e.g. because [`Features::ISRC`] was not requested or is not
+    /// supported on the current platform.
+    ///
+    /// [`Features::ISRC`]: ./struct.Features.html#associatedconstant.ISRC
+    #[default]
+    NotRead,
+
+    /// ISRCs have been read, but the track has none.
+    NotPresent,
+
+    /// The disc reported an ISRC which is not valid, e.g. because the subchannel data was
+    /// garbled. Holds the raw value.
+    Invalid(String),
+
+    /// A valid ISRC.
+    Valid(Isrc),
+}
+
+impl TrackIsrc {
+    /// Classifies an ISRC as read from a disc.
+    ///
+    /// An empty string and the all-zero ISRC `000000000000`, which some drives report for
+    /// tracks without ISRC, result in [`TrackIsrc::NotPresent`].
+    ///
+    /// [`TrackIsrc::NotPresent`]: #variant.NotPresent
+    pub fn from_raw(raw: &str) -> TrackIsrc {
+        if raw.is_empty() || (raw.len() == ISRC_LENGTH && raw.bytes().all(|c| c == b'0')) {
+            TrackIsrc::NotPresent
+        } else {
+            raw.parse()
+                .map(TrackIsrc::Valid)
+                .unwrap_or_else(|_| TrackIsrc::Invalid(raw.to_string()))
+        }
+    }
+
+    /// The ISRC, if valid.
+    pub fn isrc(&self) -> Option<&Isrc> {
+        match self {
+            TrackIsrc::Valid(isrc) => Some(isrc),
+            _ => None,
+        }
+    }
+
+    /// The raw ISRC as string. Empty if no ISRC was read or present.
+    pub fn as_str(&self) -> &str {
+        match self {
+            TrackIsrc::NotRead | TrackIsrc::NotPresent => "",
+            TrackIsrc::Invalid(raw) => raw,
+            TrackIsrc::Valid(isrc) => isrc.as_str(),
+        }
+    }
+}
+
+impl fmt::Display for TrackIsrc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<Isrc> for TrackIsrc {
+    fn from(isrc: Isrc) -> TrackIsrc {
+        TrackIsrc::Valid(isrc)
+    }
+}
+
+/// Deserializes `null` as [`TrackIsrc::NotRead`] and strings with [`TrackIsrc::from_raw`].
+impl From<Option<String>> for TrackIsrc {
+    fn from(raw: Option<String>) -> TrackIsrc {
+        raw.map_or(TrackIsrc::NotRead, |raw| TrackIsrc::from_raw(&raw))
+    }
+}
+
+impl From<TrackIsrc> for Option<String> {
+    fn from(isrc: TrackIsrc) -> Option<String> {
+        match isrc {
+            TrackIsrc::NotRead => None,
+            TrackIsrc::NotPresent => Some(String::new()),
+            TrackIsrc::Invalid(raw) => Some(raw),
+            TrackIsrc::Valid(isrc) => Some(isrc.0),
+        }
+    }
+}
+
+/// Classifies the ISRCs given in a file like a cue sheet, one entry per track.
+///
+/// Tracks without ISRC are [`TrackIsrc::NotPresent`] if the file lists ISRCs for other
+/// tracks. Otherwise the ISRCs were most likely not read when creating the file.
+pub(crate) fn track_isrcs(isrcs: Vec<Option<String>>) -> Vec<TrackIsrc> {
+    let missing = if isrcs.iter().any(Option::is_some) {
+        TrackIsrc::NotPresent
+    } else {
+        TrackIsrc::NotRead
+    };
+    isrcs
+        .into_iter()
+        .map(|isrc| isrc.map_or_else(|| missing.clone(), |isrc| TrackIsrc::from_raw(&isrc)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{track_isrcs, Isrc, TrackIsrc};
+    use crate::DiscError;
+
+    #[test]
+    fn isrc_parse() {
+        let isrc: Isrc = "deE250800230".parse().unwrap();
+        assert_eq!("DEE250800230", isrc.as_str());
+        assert_eq!("DEE250800230", isrc.to_string());
+        assert_eq!("DE", isrc.country_code());
+        assert_eq!("E25", isrc.registrant());
+        assert_eq!(8, isrc.year());
+        assert_eq!(230, isrc.designation_code());
+    }
+
+    #[test]
+    fn isrc_parse_invalid() {
+        for invalid in &[
+            "",
+            "DEE25080023",
+            "DEE2508002300",
+            "D1E250800230",
+            "DEE2-0800230",
+            "DEE25A800230",
+            "DEE2508002X0",
+            "DE-E25-08-00230",
+            "000000000000",
+        ] {
+            assert_eq!(
+                Err(DiscError::parse(0, "Invalid ISRC")),
+                invalid.parse::<Isrc>(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn isrc_track_isrc_from_raw() {
+        let valid = TrackIsrc::from_raw("GBAYE9300106");
+        assert_eq!(Some(&"GBAYE9300106".parse().unwrap()), valid.isrc());
+        assert_eq!("GBAYE9300106", valid.as_str());
+        assert_eq!(TrackIsrc::NotPresent, TrackIsrc::from_raw(""));
+        assert_eq!(TrackIsrc::NotPresent, TrackIsrc::from_raw("000000000000"));
+        assert_eq!(
+            TrackIsrc::Invalid("00000000000".to_string()),
+            TrackIsrc::from_raw("00000000000")
+        );
+        let invalid = TrackIsrc::from_raw("GB\u{1}YE9300106");
+        assert_eq!(None, invalid.isrc());
+        assert_eq!("GB\u{1}YE9300106", invalid.to_string());
+        assert_eq!("", TrackIsrc::NotRead.as_str());
+        assert_eq!(TrackIsrc::NotRead, TrackIsrc::default());
+    }
+
+    #[test]
+    fn isrc_track_isrcs() {
+        assert_eq!(
+            vec![
+                TrackIsrc::from_raw("GBAYE9300106"),
+                TrackIsrc::NotPresent,
+                TrackIsrc::Invalid("X".to_string())
+            ],
+            track_isrcs(vec![
+                Some("GBAYE9300106".to_string()),
+                None,
+                Some("X".to_string())
+            ])
+        );
+        assert_eq!(
+            vec![TrackIsrc::NotRead, TrackIsrc::NotRead],
+            track_isrcs(vec![None, None])
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn isrc_track_isrc_serde() {
+        for isrc in &[
+            TrackIsrc::NotRead,
+            TrackIsrc::NotPresent,
+            TrackIsrc::Invalid("X".to_string()),
+            TrackIsrc::from_raw("GBAYE9300106"),
+        ] {
+            let json = serde_json::to_string(isrc).expect("serializing failed");
+            let deserialized: TrackIsrc =
+                serde_json::from_str(&json).expect("deserializing failed");
+            assert_eq!(isrc, &deserialized);
+        }
+        assert_eq!(
+            "\"GBAYE9300106\"",
+            serde_json::to_string(&TrackIsrc::from_raw("GBAYE9300106")).unwrap()
+        );
+    }
+}
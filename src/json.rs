@@ -0,0 +1,356 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A minimal JSON parser for the responses of web services.
+//!
+//! Numbers are kept as `f64`, which is sufficient for the sector counts and lengths used by
+//! the web services. Object members keep their order.
+
+use crate::DiscError;
+
+/// Maximum nesting depth of arrays and objects, to avoid overflowing the stack on malicious
+/// input.
+const MAX_DEPTH: usize = 128;
+
+/// A JSON value.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// The member `key` of an object.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// The value as string, if it is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The value as integer, if it is a number without fractional part.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Number(value) if value.fract() == 0.0 => Some(*value as i64),
+            _ => None,
+        }
+    }
+
+    /// The elements of an array, or an empty slice for other values.
+    pub fn elements(&self) -> &[Value] {
+        match self {
+            Value::Array(elements) => elements,
+            _ => &[],
+        }
+    }
+
+    /// The string member `key` of an object, if present and not empty.
+    pub fn str_member(&self, key: &str) -> Option<String> {
+        self.get(key)
+            .and_then(Value::as_str)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    }
+}
+
+/// Parses a JSON document.
+pub(crate) fn parse(document: &str) -> Result<Value, DiscError> {
+    let mut parser = Parser {
+        input: document.as_bytes(),
+        position: 0,
+        depth: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.position < parser.input.len() {
+        return Err(parser.error("Unexpected content after JSON value"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> DiscError {
+        DiscError::parse(0, &format!("{} at byte {}", message, self.position))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), DiscError> {
+        self.skip_whitespace();
+        if self.peek() != Some(byte) {
+            return Err(self.error(&format!("Expected '{}'", char::from(byte))));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, literal: &str, value: Value) -> Result<Value, DiscError> {
+        if self.input[self.position..].starts_with(literal.as_bytes()) {
+            self.position += literal.len();
+            Ok(value)
+        } else {
+            Err(self.error("Invalid literal"))
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, DiscError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.nested(Parser::object),
+            Some(b'[') => self.nested(Parser::array),
+            Some(b'"') => Ok(Value::String(self.string()?)),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(b'-') | Some(b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("Unexpected character")),
+            None => Err(self.error("Unexpected end of JSON")),
+        }
+    }
+
+    /// Parses an array or object with `parse`, limiting the nesting depth.
+    fn nested(
+        &mut self,
+        parse: fn(&mut Parser<'a>) -> Result<Value, DiscError>,
+    ) -> Result<Value, DiscError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("Values nested too deeply"));
+        }
+        self.depth += 1;
+        let value = parse(self)?;
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    fn object(&mut self) -> Result<Value, DiscError> {
+        self.position += 1;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Value::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("Expected member name"));
+            }
+            let name = self.string()?;
+            self.expect(b':')?;
+            members.push((name, self.value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Value::Object(members));
+                }
+                _ => return Err(self.error("Expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, DiscError> {
+        self.position += 1;
+        let mut elements = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Value::Array(elements));
+        }
+        loop {
+            elements.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Value::Array(elements));
+                }
+                _ => return Err(self.error("Expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, DiscError> {
+        let start = self.position;
+        while let Some(b'-') | Some(b'+') | Some(b'.') | Some(b'e') | Some(b'E')
+        | Some(b'0'..=b'9') = self.peek()
+        {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.input[start..self.position])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .map(Value::Number)
+            .ok_or_else(|| self.error("Invalid number"))
+    }
+
+    fn string(&mut self) -> Result<String, DiscError> {
+        self.position += 1;
+        let mut result = Vec::new();
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.position += 1;
+                    break;
+                }
+                Some(b'\\') => {
+                    self.position += 1;
+                    let escaped = match self.peek() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.unicode_escape()?,
+                        _ => return Err(self.error("Invalid escape sequence")),
+                    };
+                    self.position += 1;
+                    let mut buffer = [0; 4];
+                    result.extend_from_slice(escaped.encode_utf8(&mut buffer).as_bytes());
+                }
+                Some(byte) => {
+                    result.push(byte);
+                    self.position += 1;
+                }
+                None => return Err(self.error("Unterminated string")),
+            }
+        }
+        String::from_utf8(result).map_err(|_| self.error("Invalid UTF-8 in string"))
+    }
+
+    /// Parses `uXXXX`, including a following low surrogate, and leaves the position at the
+    /// last hex digit.
+    fn unicode_escape(&mut self) -> Result<char, DiscError> {
+        let high = self.hex4()?;
+        let code = if (0xd800..0xdc00).contains(&high)
+            && self.input[self.position + 1..].starts_with(b"\\u")
+        {
+            self.position += 2;
+            let low = self.hex4()?;
+            0x10000 + ((high - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff)
+        } else {
+            high
+        };
+        std::char::from_u32(code).ok_or_else(|| self.error("Invalid unicode escape"))
+    }
+
+    /// Parses the four hex digits after the current `u`.
+    fn hex4(&mut self) -> Result<u32, DiscError> {
+        let digits = self
+            .input
+            .get(self.position + 1..self.position + 5)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error("Invalid unicode escape"))?;
+        self.position += 4;
+        Ok(digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Value};
+    use crate::DiscError;
+
+    #[test]
+    fn json_parse() {
+        let value = parse(
+            r#" {"id": "abc", "count": 10, "ratio": -1.25e1, "ok": true, "none": null,
+                "list": [1, "two", {}], "empty": [], "text": "a\"b\\c\né😀",
+                "escaped": "é😀"} "#,
+        )
+        .unwrap();
+        assert_eq!(Some("abc"), value.get("id").and_then(Value::as_str));
+        assert_eq!(Some(10), value.get("count").and_then(Value::as_i64));
+        assert_eq!(Some(&Value::Number(-12.5)), value.get("ratio"));
+        assert_eq!(None, value.get("ratio").and_then(Value::as_i64));
+        assert_eq!(Some(&Value::Bool(true)), value.get("ok"));
+        assert_eq!(Some(&Value::Null), value.get("none"));
+        assert_eq!(3, value.get("list").unwrap().elements().len());
+        assert!(value.get("empty").unwrap().elements().is_empty());
+        assert_eq!(Some("a\"b\\c\né😀".to_string()), value.str_member("text"));
+        assert_eq!(Some("é😀".to_string()), value.str_member("escaped"));
+        assert_eq!(None, value.get("missing"));
+    }
+
+    #[test]
+    fn json_parse_errors() {
+        assert_eq!(
+            Err(DiscError::parse(0, "Unexpected end of JSON at byte 0")),
+            parse("")
+        );
+        assert_eq!(
+            Err(DiscError::parse(0, "Expected ',' or '}' at byte 8")),
+            parse(r#"{"a": 1 "b": 2}"#)
+        );
+        assert_eq!(
+            Err(DiscError::parse(0, "Unterminated string at byte 4")),
+            parse(r#"["ab"#)
+        );
+        assert_eq!(
+            Err(DiscError::parse(
+                0,
+                "Unexpected content after JSON value at byte 3"
+            )),
+            parse("[] x")
+        );
+        assert_eq!(
+            Err(DiscError::parse(0, "Values nested too deeply at byte 128")),
+            parse(&"[".repeat(200_000))
+        );
+    }
+
+    #[test]
+    fn json_parse_max_depth() {
+        let document = format!("{}{}", "[".repeat(128), "]".repeat(128));
+        assert!(parse(&document).is_ok());
+        let document = format!("{}{}", "{\"a\":".repeat(129), "}".repeat(129));
+        assert!(parse(&document).is_err());
+    }
+}
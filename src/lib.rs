@@ -19,6 +19,21 @@
 //! library. In addition to calculating the disc IDs you can also get advanced data from the
 //! audio CD such as MCN (media catalogue number) and per-track ISRCs.
 //!
+//! The bindings are enabled by the default `libdiscid` feature. If you only need to calculate
+//! disc IDs from a known TOC you can disable default features and use the pure Rust
+//! implementation in the [`native`] module instead, which does not require libdiscid to be
+//! installed.
+//!
+//! Disc IDs can also be calculated for disc images without access to the physical disc, see
+//! the [`cue`] and [`cdrdao`] modules for reading CUE sheets and cdrdao TOC files and the
+//! [`eac`], [`xld`] and [`whipper`] modules for reading ripper log files. The
+//! [`tool_output`] module parses the TOC output of command line tools like cdparanoia.
+//!
+//! The [`accuraterip`] and [`ctdb`] modules provide the disc IDs and checksums used by
+//! AccurateRip and the CUETools Database to verify rips. The [`cddb`] module looks up discs
+//! on FreeDB compatible servers, which return [`xmcd`] database entries. The [`musicbrainz`]
+//! module looks up disc IDs with the MusicBrainz web service.
+//!
 //! To get started see the documentation and examples of [`DiscId::read`], [`DiscId::read_features`],
 //! [`DiscId::put`] and [`DiscId::parse`].
 //!
@@ -32,6 +47,28 @@
 //! [`DiscId::read_features`]: ./struct.DiscId.html#method.read_features
 //! [`DiscId::put`]: ./struct.DiscId.html#method.put
 //! [`DiscId::parse`]: ./struct.DiscId.html#method.parse
+//! [`native`]: ./native/index.html
+//! [`cue`]: ./cue/index.html
+//! [`cdrdao`]: ./cdrdao/index.html
+//! [`eac`]: ./eac/index.html
+//! [`xld`]: ./xld/index.html
+//! [`whipper`]: ./whipper/index.html
+//! [`tool_output`]: ./tool_output/index.html
+//! [`accuraterip`]: ./accuraterip/index.html
+//! [`ctdb`]: ./ctdb/index.html
+//! [`cddb`]: ./cddb/index.html
+//! [`xmcd`]: ./xmcd/index.html
+//! [`musicbrainz`]: ./musicbrainz/index.html
+//!
+//! # Features
+//!
+//! - `libdiscid` (default): Bindings to libdiscid, providing [`DiscId`].
+//! - `serde`: Implements `Serialize` and `Deserialize` for [`Toc`], [`Track`] and [`DiscInfo`].
+//!
+//! [`DiscId`]: ./struct.DiscId.html
+//! [`Toc`]: ./struct.Toc.html
+//! [`Track`]: ./struct.Track.html
+//! [`DiscInfo`]: ./struct.DiscInfo.html
 
 #![deny(
     missing_docs,
@@ -44,19 +81,56 @@
     unused_qualifications
 )]
 
+#[cfg(feature = "libdiscid")]
 use discid_sys::*;
-use std::error::Error;
+#[cfg(feature = "libdiscid")]
 use std::ffi::{CStr, CString};
+#[cfg(feature = "libdiscid")]
 use std::fmt;
-use std::num::ParseIntError;
+#[cfg(feature = "libdiscid")]
 use std::os::raw::c_char;
+#[cfg(feature = "libdiscid")]
 use std::os::raw::c_int;
+#[cfg(feature = "libdiscid")]
 use std::ptr;
-use std::rc::Rc;
+#[cfg(feature = "libdiscid")]
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 
+#[cfg(feature = "libdiscid")]
 #[macro_use]
 extern crate bitflags;
 
+pub mod accuraterip;
+pub mod cddb;
+pub mod cdrdao;
+pub mod ctdb;
+pub mod cue;
+pub mod eac;
+mod error;
+mod ids;
+mod info;
+mod isrc;
+mod json;
+mod log;
+mod msf;
+pub mod musicbrainz;
+pub mod native;
+mod toc;
+pub mod tool_output;
+pub mod whipper;
+pub mod xld;
+pub mod xmcd;
+mod xml;
+
+#[cfg(feature = "libdiscid")]
+use crate::accuraterip::AccurateRipId;
+pub use crate::error::{DiscError, DiscIdMismatch, TocErrorKind};
+pub use crate::ids::{FreedbId, MusicBrainzDiscId};
+pub use crate::info::DiscInfo;
+pub use crate::isrc::{Isrc, TrackIsrc};
+pub use crate::toc::Toc;
+
+#[cfg(feature = "libdiscid")]
 bitflags! {
     /// Constants representing the features supported by libdiscid.
     ///
@@ -82,71 +156,74 @@ bitflags! {
         /// Supports reading the ISRCs per track.
         ///
         /// For each track read the ISRC encoded in the subchannel data. Not all CDs provide this
-        /// information. Without this feature [`Track::isrc`] will always be an empty string.
+        /// information. Without this feature [`Track::isrc`] will always be [`TrackIsrc::NotRead`].
         ///
         /// [`Track::isrc`]: ./struct.Track.html#structfield.isrc
+        /// [`TrackIsrc::NotRead`]: ./enum.TrackIsrc.html#variant.NotRead
         const ISRC = discid_feature::DISCID_FEATURE_ISRC.0;
     }
 }
 
+#[cfg(feature = "libdiscid")]
 impl Features {
     fn as_bitfield(&self) -> discid_feature {
         discid_feature(self.bits())
     }
 }
 
-/// This is returned on errors reading the disc or setting the TOC.
+/// Pointer to the libdiscid handle.
+///
+/// libdiscid has no thread local state, so the handle can be moved between threads.
+#[cfg(feature = "libdiscid")]
 #[derive(Debug)]
-pub struct DiscError {
-    reason: String,
-}
+struct RawHandle(ptr::NonNull<discid_sys::DiscId>);
 
-impl DiscError {
-    fn new(message: &str) -> Self {
-        DiscError {
-            reason: message.to_string(),
-        }
-    }
-}
-
-impl Error for DiscError {}
-
-impl fmt::Display for DiscError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "DiscError: {}", self.reason)
-    }
-}
+#[cfg(feature = "libdiscid")]
+unsafe impl Send for RawHandle {}
 
-impl From<ParseIntError> for DiscError {
-    fn from(err: ParseIntError) -> Self {
-        DiscError {
-            reason: format!("{}", err),
-        }
+#[cfg(feature = "libdiscid")]
+impl RawHandle {
+    fn as_ptr(&self) -> *mut discid_sys::DiscId {
+        self.0.as_ptr()
     }
 }
 
+/// Owns the libdiscid handle and serializes all access to it.
+///
+/// libdiscid calculates values like the disc ID lazily on first access and stores them in
+/// the handle, so even the getters must not be called concurrently.
+#[cfg(feature = "libdiscid")]
 #[derive(Debug)]
 struct DiscIdHandle {
-    real_handle: ptr::NonNull<discid_sys::DiscId>,
+    real_handle: Mutex<RawHandle>,
 }
 
+#[cfg(feature = "libdiscid")]
 impl DiscIdHandle {
     fn new(handle: *mut discid_sys::DiscId) -> DiscIdHandle {
         unsafe {
             DiscIdHandle {
-                real_handle: ptr::NonNull::new_unchecked(handle),
+                real_handle: Mutex::new(RawHandle(ptr::NonNull::new_unchecked(handle))),
             }
         }
     }
 
-    fn as_ptr(&self) -> *mut discid_sys::DiscId {
-        self.real_handle.as_ptr()
+    fn lock(&self) -> MutexGuard<'_, RawHandle> {
+        // The handle does not hold any invariants a panic could break.
+        self.real_handle
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
     }
 }
 
+#[cfg(feature = "libdiscid")]
 impl Drop for DiscIdHandle {
     fn drop(&mut self) {
-        unsafe { discid_free(self.as_ptr()) }
+        let handle = self
+            .real_handle
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        unsafe { discid_free(handle.as_ptr()) }
     }
 }
 
@@ -155,24 +232,42 @@ impl Drop for DiscIdHandle {
 /// Use [`DiscId::read`], [`DiscId::read_features`], [`DiscId::put`] or [`DiscId::parse`]
 /// to initialize an instance of `DiscId`.
 ///
+/// `DiscId` is `Send` and `Sync`, so a read disc can be moved to another thread or shared
+/// using an `Arc`. Access to the underlying libdiscid handle is synchronized internally.
+///
+/// # Examples
+///
+/// ```
+/// use discid::DiscId;
+/// use std::thread;
+///
+/// let disc = DiscId::parse("1 2 2000 150 1000").expect("DiscId::parse() failed");
+/// let handle = thread::spawn(move || disc.tracks().count());
+/// assert_eq!(2, handle.join().unwrap());
+/// ```
+///
 /// [`DiscId::read`]: #method.read
 /// [`DiscId::read_features`]: #method.read_features
 /// [`DiscId::put`]: #method.put
 /// [`DiscId::parse`]: #method.parse
+#[cfg(feature = "libdiscid")]
 pub struct DiscId {
-    handle: Rc<DiscIdHandle>,
+    handle: Arc<DiscIdHandle>,
+    isrc_read: bool,
 }
 
+#[cfg(feature = "libdiscid")]
 impl DiscId {
     fn new() -> Result<DiscId, DiscError> {
         let handle = unsafe { discid_new() };
         if handle.is_null() {
-            Err(DiscError::new(
-                "discid_new() failed, could not allocate memory",
+            Err(DiscError::Other(
+                "discid_new() failed, could not allocate memory".to_string(),
             ))
         } else {
             Ok(DiscId {
-                handle: Rc::new(DiscIdHandle::new(handle)),
+                handle: Arc::new(DiscIdHandle::new(handle)),
+                isrc_read: false,
             })
         }
     }
@@ -244,13 +339,16 @@ impl DiscId {
     /// [`Features`]: ./struct.Features.html
     /// [`Features::READ`]: ./struct.Features.html#associatedconstant.READ
     pub fn read_features(device: Option<&str>, features: Features) -> Result<DiscId, DiscError> {
-        let disc = DiscId::new()?;
+        let mut disc = DiscId::new()?;
+        disc.isrc_read = features.contains(Features::ISRC) && DiscId::has_feature(Features::ISRC);
         let c_device: *const c_char = match device {
             Some(d) => CString::new(d).expect("CString::new failed").into_raw(),
             None => ptr::null(),
         };
+        let handle = disc.handle.lock();
         let status =
-            unsafe { discid_read_sparse(disc.handle.as_ptr(), c_device, features.as_bitfield()) };
+            unsafe { discid_read_sparse(handle.as_ptr(), c_device, features.as_bitfield()) };
+        drop(handle);
         if status == 0 {
             Err(disc.error())
         } else {
@@ -302,7 +400,9 @@ impl DiscId {
             offset_ptr = offsets.as_ptr() as *mut c_int;
         }
 
-        let status = unsafe { discid_put(disc.handle.as_ptr(), first, last, offset_ptr) };
+        let handle = disc.handle.lock();
+        let status = unsafe { discid_put(handle.as_ptr(), first, last, offset_ptr) };
+        drop(handle);
         if status == 0 {
             Err(disc.error())
         } else {
@@ -331,43 +431,47 @@ impl DiscId {
     /// [`DiscId`]: ./struct.DiscId.html
     /// [`toc_string`]: #method.toc_string
     pub fn parse(toc: &str) -> Result<DiscId, DiscError> {
-        let mut i: usize = 0;
-        let mut first_track: c_int = 1;
-        let mut last_track: c_int = 1;
-        let mut offsets: [c_int; 100] = [0; 100];
-        for part in toc.split(' ') {
-            let parsed_int = part.parse::<c_int>()?;
-            if i == 0 {
-                first_track = parsed_int;
-            } else if i == 1 {
-                last_track = parsed_int;
-            } else if i > 1 {
-                if i > (last_track as usize + 2) || i > 99 + 2 {
-                    return Err(DiscError::new(
-                        "TOC string contains too many offsets (max. 100)",
-                    ));
-                }
-
-                offsets[i - 2] = parsed_int;
-            }
-
-            i += 1;
-        }
-
-        if i < 3 {
-            return Err(DiscError::new(&format!("Invalid TOC string {:?}", toc)));
-        }
+        let toc: Toc = toc.parse()?;
+        DiscId::from_toc(&toc)
+    }
 
-        let offset_count = (i - 3) as c_int;
-        let track_count = last_track - first_track + 1;
-        if track_count != offset_count {
-            return Err(DiscError::new(&format!(
-                "Number of offsets {} does not match track count {}",
-                offset_count, track_count
-            )));
-        }
+    /// Returns a [`DiscId`] instance for the given [`Toc`].
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use discid::{DiscId, Toc};
+    ///
+    /// let toc = Toc::new(1, 2, 2000, vec![150, 1000]).expect("Toc::new() failed");
+    /// let disc = DiscId::from_toc(&toc).expect("DiscId::from_toc() failed");
+    /// assert_eq!(toc.id(), disc.id());
+    /// ```
+    ///
+    /// [`DiscId`]: ./struct.DiscId.html
+    /// [`Toc`]: ./struct.Toc.html
+    pub fn from_toc(toc: &Toc) -> Result<DiscId, DiscError> {
+        DiscId::put(toc.first_track(), &toc.offsets_with_lead_out())
+    }
 
-        DiscId::put(first_track, &offsets[0..(i - 2)])
+    /// Returns a [`DiscId`] instance for the TOC given in a MusicBrainz URL.
+    ///
+    /// Fails if the disc ID or track count given in the URL do not match the TOC, see
+    /// [`musicbrainz::parse_url`] for details.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use discid::DiscId;
+    ///
+    /// let url = "https://musicbrainz.org/cdtoc/attach?id=MS0ykA8iqhwhwey1JFwbNd4IIqE-&tracks=2&toc=1+2+39738+150+18901";
+    /// let disc = DiscId::from_url(url).expect("DiscId::from_url() failed");
+    /// assert_eq!("MS0ykA8iqhwhwey1JFwbNd4IIqE-", disc.id());
+    /// ```
+    ///
+    /// [`DiscId`]: ./struct.DiscId.html
+    /// [`musicbrainz::parse_url`]: ./musicbrainz/fn.parse_url.html
+    pub fn from_url(url: &str) -> Result<DiscId, DiscError> {
+        DiscId::from_toc(&musicbrainz::parse_url(url)?)
     }
 
     /// Check if a certain feature is implemented on the current platform.
@@ -420,19 +524,22 @@ impl DiscId {
     }
 
     fn error(&self) -> DiscError {
-        let str_ptr = unsafe { discid_get_error_msg(self.handle.as_ptr()) };
-        DiscError::new(&to_str(str_ptr))
+        let handle = self.handle.lock();
+        let str_ptr = unsafe { discid_get_error_msg(handle.as_ptr()) };
+        DiscError::from_libdiscid(&to_str(str_ptr))
     }
 
     /// The MusicBrainz disc ID.
     pub fn id(&self) -> String {
-        let str_ptr = unsafe { discid_get_id(self.handle.as_ptr()) };
+        let handle = self.handle.lock();
+        let str_ptr = unsafe { discid_get_id(handle.as_ptr()) };
         to_str(str_ptr)
     }
 
     /// The FreeDB disc ID.
     pub fn freedb_id(&self) -> String {
-        let str_ptr = unsafe { discid_get_freedb_id(self.handle.as_ptr()) };
+        let handle = self.handle.lock();
+        let str_ptr = unsafe { discid_get_freedb_id(handle.as_ptr()) };
         to_str(str_ptr)
     }
 
@@ -450,34 +557,91 @@ impl DiscId {
     ///
     /// [`DiscId::parse`]: #method.parse
     pub fn toc_string(&self) -> String {
-        let str_ptr = unsafe { discid_get_toc_string(self.handle.as_ptr()) };
+        let handle = self.handle.lock();
+        let str_ptr = unsafe { discid_get_toc_string(handle.as_ptr()) };
         to_str(str_ptr)
     }
 
+    /// Returns the TOC of this disc as a [`Toc`].
+    ///
+    /// See also [`DiscId::toc_string`].
+    ///
+    /// [`Toc`]: ./struct.Toc.html
+    /// [`DiscId::toc_string`]: #method.toc_string
+    pub fn toc(&self) -> Toc {
+        let handle = self.handle.lock();
+        let handle_ptr = handle.as_ptr();
+        let first = unsafe { discid_get_first_track_num(handle_ptr) };
+        let last = unsafe { discid_get_last_track_num(handle_ptr) };
+        let sectors = unsafe { discid_get_sectors(handle_ptr) };
+        let offsets = (first..=last)
+            .map(|number| unsafe { discid_get_track_offset(handle_ptr, number) })
+            .collect();
+        Toc::new_unchecked(first, last, sectors, offsets)
+    }
+
+    /// The AccurateRip disc ID.
+    ///
+    /// For enhanced CDs the length of the data track is not known, use
+    /// [`AccurateRipId::from_enhanced_cd`] in this case.
+    ///
+    /// [`AccurateRipId::from_enhanced_cd`]: ./accuraterip/struct.AccurateRipId.html#method.from_enhanced_cd
+    pub fn accuraterip_id(&self) -> AccurateRipId {
+        AccurateRipId::from_toc(&self.toc())
+    }
+
+    /// The CUETools Database TOC ID.
+    ///
+    /// See [`ctdb::toc_id`] for details.
+    ///
+    /// [`ctdb::toc_id`]: ./ctdb/fn.toc_id.html
+    pub fn ctdb_toc_id(&self) -> String {
+        ctdb::toc_id(&self.toc())
+    }
+
+    /// The `cddb query` command for looking up this disc on a CDDB server.
+    ///
+    /// See [`cddb::query_command`] for details.
+    ///
+    /// [`cddb::query_command`]: ./cddb/fn.query_command.html
+    pub fn cddb_query_command(&self) -> String {
+        cddb::query_command(&self.toc())
+    }
+
     /// An URL for submitting the DiscID to MusicBrainz.
+    ///
+    /// The URL is generated by libdiscid and always points to `http://musicbrainz.org`. Use
+    /// [`UrlBuilder`] for HTTPS URLs and other servers.
+    ///
+    /// [`UrlBuilder`]: ./musicbrainz/struct.UrlBuilder.html
     pub fn submission_url(&self) -> String {
-        let str_ptr = unsafe { discid_get_submission_url(self.handle.as_ptr()) };
+        let handle = self.handle.lock();
+        let str_ptr = unsafe { discid_get_submission_url(handle.as_ptr()) };
         to_str(str_ptr)
     }
 
     /// The number of the first track on this disc.
     pub fn first_track_num(&self) -> i32 {
-        unsafe { discid_get_first_track_num(self.handle.as_ptr()) }
+        let handle = self.handle.lock();
+        unsafe { discid_get_first_track_num(handle.as_ptr()) }
     }
 
     /// The number of the last track on this disc.
     pub fn last_track_num(&self) -> i32 {
-        unsafe { discid_get_last_track_num(self.handle.as_ptr()) }
+        let handle = self.handle.lock();
+        unsafe { discid_get_last_track_num(handle.as_ptr()) }
     }
 
     /// The length of the disc in sectors.
     pub fn sectors(&self) -> i32 {
-        unsafe { discid_get_sectors(self.handle.as_ptr()) }
+        let handle = self.handle.lock();
+        unsafe { discid_get_sectors(handle.as_ptr()) }
     }
 
     /// The media catalogue number on the disc, if present.
     pub fn mcn(&self) -> String {
-        let str_ptr = unsafe { discid_get_mcn(self.handle.as_ptr()) };
+        let handle = self.handle.lock();
+        let str_ptr = unsafe { discid_get_mcn(handle.as_ptr()) };
         to_str(str_ptr)
     }
 
@@ -504,7 +668,7 @@ impl DiscId {
     ///
     /// [`Track`]: ./struct.Track.html
     pub fn tracks(&self) -> TrackIter {
-        TrackIter::new(Rc::clone(&self.handle))
+        TrackIter::new(Arc::clone(&self.handle), self.isrc_read)
     }
 
     /// Returns a [`Track`] instance for the nth track.
@@ -540,10 +704,11 @@ impl DiscId {
                 number, first, last
             );
         }
-        get_track(Rc::clone(&self.handle), number)
+        get_track(&self.handle, number, self.isrc_read)
     }
 }
 
+#[cfg(feature = "libdiscid")]
 impl fmt::Debug for DiscId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "DiscId {}", self.toc_string())
@@ -551,7 +716,8 @@ impl fmt::Debug for DiscId {
 }
 
 /// Holds information about a single track
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Track {
     /// Track number (1-99) of the track.
     pub number: i32,
@@ -562,40 +728,49 @@ pub struct Track {
     /// Track length in sectors.
     pub sectors: i32,
 
-    /// ISRC for this track (might be empty).
+    /// ISRC for this track, or the reason why it is not available.
     ///
-    /// This will only bet set if [`DiscId::read_features`] is called with [`Features::ISRC`].
+    /// This will only be read if [`DiscId::read_features`] is called with [`Features::ISRC`].
     ///
     /// [`DiscId::read_features`]: ./struct.DiscId.html#method.read_features
     /// [`Features::ISRC`]: ./struct.Features.html#associatedconstant.ISRC
-    pub isrc: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub isrc: TrackIsrc,
 }
 
 /// Allows iterating over all tracks of a read disc.
 ///
-/// Returns an instance of [`Track`] for each track.
+/// Returns an instance of [`Track`] for each track. Like [`DiscId`], `TrackIter` is `Send`
+/// and `Sync`.
 ///
 /// [`Track`]: ./struct.Track.html
+/// [`DiscId`]: ./struct.DiscId.html
+#[cfg(feature = "libdiscid")]
 #[derive(Debug)]
 pub struct TrackIter {
-    handle: Rc<DiscIdHandle>,
+    handle: Arc<DiscIdHandle>,
     curr: i32,
     last_track: i32,
+    isrc_read: bool,
 }
 
+#[cfg(feature = "libdiscid")]
 impl TrackIter {
-    fn new(handle: Rc<DiscIdHandle>) -> TrackIter {
-        let handle_ptr = handle.as_ptr();
-        let first_track = unsafe { discid_get_first_track_num(handle_ptr) };
-        let last_track = unsafe { discid_get_last_track_num(handle_ptr) };
+    fn new(handle: Arc<DiscIdHandle>, isrc_read: bool) -> TrackIter {
+        let raw_handle = handle.lock();
+        let first_track = unsafe { discid_get_first_track_num(raw_handle.as_ptr()) };
+        let last_track = unsafe { discid_get_last_track_num(raw_handle.as_ptr()) };
+        drop(raw_handle);
         TrackIter {
             handle,
             curr: first_track,
             last_track,
+            isrc_read,
         }
     }
 }
 
+#[cfg(feature = "libdiscid")]
 impl Iterator for TrackIter {
     type Item = Track;
 
@@ -603,33 +778,44 @@ impl Iterator for TrackIter {
         let track_num = self.curr;
         self.curr += 1;
         if track_num <= self.last_track {
-            Some(get_track(Rc::clone(&self.handle), track_num))
+            Some(get_track(&self.handle, track_num, self.isrc_read))
         } else {
             None
         }
     }
 }
 
-fn get_track(handle: Rc<DiscIdHandle>, number: i32) -> Track {
+#[cfg(feature = "libdiscid")]
+fn get_track(handle: &DiscIdHandle, number: i32, isrc_read: bool) -> Track {
+    let handle = handle.lock();
     let handle_ptr = handle.as_ptr();
-    let isrc_ptr = unsafe { discid_get_track_isrc(handle_ptr, number) };
+    let isrc = if isrc_read {
+        let isrc_ptr = unsafe { discid_get_track_isrc(handle_ptr, number) };
+        TrackIsrc::from_raw(&to_str(isrc_ptr))
+    } else {
+        TrackIsrc::NotRead
+    };
     Track {
         number,
         offset: unsafe { discid_get_track_offset(handle_ptr, number) },
         sectors: unsafe { discid_get_track_length(handle_ptr, number) },
-        isrc: to_str(isrc_ptr),
+        isrc,
     }
 }
 
+#[cfg(feature = "libdiscid")]
 fn to_str(c_buf: *const c_char) -> String {
     let c_str: &CStr = unsafe { CStr::from_ptr(c_buf) };
     let str_slice = c_str.to_string_lossy();
     str_slice.into_owned()
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "libdiscid"))]
 mod tests {
-    use super::{DiscError, DiscId, Features, Track};
+    use super::{DiscError, DiscId, Features, Toc, TocErrorKind, Track, TrackIsrc, TrackIter};
+    use std::num::IntErrorKind;
+    use std::sync::Arc;
+    use std::thread;
 
     #[test]
     #[ignore]
@@ -655,7 +841,7 @@ mod tests {
             DiscId::read_features(None, Features::all()).expect("DiscId::read_features failed");
         assert_eq!(28, disc.id().len());
         assert!(!disc.mcn().is_empty());
-        assert_eq!(12, disc.nth_track(1).isrc.len());
+        assert_eq!(12, disc.nth_track(1).isrc.as_str().len());
     }
 
     #[test]
@@ -735,6 +921,25 @@ mod tests {
         DiscId::put(first, &offsets).expect("DiscId::put failed");
     }
 
+    #[test]
+    fn discid_put_error_kind() {
+        let offsets: [i32; 101] = [0; 101];
+        match DiscId::put(1, &offsets) {
+            Err(DiscError::InvalidToc { kind, .. }) => {
+                assert_eq!(TocErrorKind::IllegalTrackLimits, kind)
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn discid_read_error_kind() {
+        match DiscId::read(Some("notadevice")) {
+            Err(DiscError::DeviceOpen(message)) => assert!(message.contains("notadevice")),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
     #[test]
     fn discid_parse() {
         let toc =
@@ -761,14 +966,20 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "invalid digit found in string")]
     fn discid_parse_invalid_nan() {
         let toc = "1 2 242457 150 a";
-        DiscId::parse(toc).expect("DiscId::parse failed");
+        match DiscId::parse(toc) {
+            Err(DiscError::ParseInt { position, source }) => {
+                assert_eq!(4, position);
+                assert_eq!(&IntErrorKind::InvalidDigit, source.kind());
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
     }
 
     #[test]
     #[should_panic(expected = "TOC string contains too many offsets")]
+    #[allow(clippy::needless_borrow)]
     fn discid_parseinvalid_too_many_offsets() {
         let toc = "1 2 242457 150 200 300";
         DiscId::parse(&toc).expect("DiscId::parse failed");
@@ -799,10 +1010,44 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "cannot parse integer from empty string")]
     fn discid_parse_invalid_empty() {
         let toc = "";
-        DiscId::parse(toc).expect("DiscId::parse failed");
+        match DiscId::parse(toc) {
+            Err(DiscError::ParseInt { position, source }) => {
+                assert_eq!(0, position);
+                assert_eq!(&IntErrorKind::Empty, source.kind());
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn discid_toc() {
+        let toc_str = "3 12 242457 150 18901 39738 59557 79152 100126 124833 147278 166336 182560";
+        let disc = DiscId::parse(toc_str).expect("DiscId::parse failed");
+        let toc = disc.toc();
+        assert_eq!(3, toc.first_track());
+        assert_eq!(12, toc.last_track());
+        assert_eq!(242457, toc.lead_out());
+        assert_eq!(toc_str, toc.to_string());
+        assert_eq!(disc.id(), toc.id());
+        assert_eq!(disc.freedb_id(), toc.freedb_id());
+    }
+
+    #[test]
+    fn discid_from_toc() {
+        let toc = Toc::new(
+            1,
+            10,
+            206535,
+            vec![
+                150, 18901, 39738, 59557, 79152, 100126, 124833, 147278, 166336, 182560,
+            ],
+        )
+        .expect("Toc::new failed");
+        let disc = DiscId::from_toc(&toc).expect("DiscId::from_toc failed");
+        assert_eq!("Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-", disc.id());
+        assert_eq!(toc, disc.toc());
     }
 
     #[test]
@@ -816,7 +1061,7 @@ mod tests {
         let expected_offset = offsets[4];
         let expected_sectors = offsets[5] - offsets[4];
         assert_eq!(4, track.number);
-        assert_eq!("", track.isrc); // Always empty for DiscId::put
+        assert_eq!(TrackIsrc::NotRead, track.isrc); // Never read for DiscId::put
         assert_eq!(
             expected_offset, track.offset,
             "track {} expected offset {}",
@@ -847,12 +1092,14 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn discid_has_feature() {
         assert_eq!(true, DiscId::has_feature(Features::READ));
     }
 
     #[test]
     #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    #[allow(clippy::bool_assert_comparison)]
     fn discid_has_feature_all() {
         assert_eq!(true, DiscId::has_feature(Features::READ));
         assert_eq!(true, DiscId::has_feature(Features::MCN));
@@ -873,6 +1120,45 @@ mod tests {
         assert_eq!("DiscId 1 2 2000 150 1000", format!("{:?}", disc));
     }
 
+    #[test]
+    fn discid_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<DiscId>();
+        assert_send_sync::<TrackIter>();
+    }
+
+    #[test]
+    fn discid_move_to_thread() {
+        let offsets = [
+            206535, 150, 18901, 39738, 59557, 79152, 100126, 124833, 147278, 166336, 182560,
+        ];
+        let disc = DiscId::put(1, &offsets).expect("DiscId::put failed");
+        let tracks = disc.tracks();
+        let handle = thread::spawn(move || {
+            assert_eq!("Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-", disc.id());
+            disc.tracks()
+                .map(|track| track.offset)
+                .collect::<Vec<i32>>()
+        });
+        assert_eq!(&offsets[1..], &handle.join().unwrap()[..]);
+        let handle = thread::spawn(move || tracks.count());
+        assert_eq!(10, handle.join().unwrap());
+    }
+
+    #[test]
+    fn discid_share_between_threads() {
+        let disc = Arc::new(DiscId::parse("1 2 2000 150 1000").expect("DiscId::parse failed"));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let disc = Arc::clone(&disc);
+                thread::spawn(move || disc.id())
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(disc.id(), handle.join().unwrap());
+        }
+    }
+
     #[test]
     fn features() {
         assert_eq!(3, (Features::READ | Features::MCN).bits());
@@ -883,40 +1169,32 @@ mod tests {
     }
 
     #[test]
-    fn track_debug() {
+    fn track_clone_eq() {
         let track = Track {
             number: 3,
             offset: 57402,
             sectors: 32960,
-            isrc: "DED831801578".to_string(),
+            isrc: TrackIsrc::from_raw("DED831801578"),
         };
-
-        assert_eq!(
-            "Track { number: 3, offset: 57402, sectors: 32960, isrc: \"DED831801578\" }",
-            format!("{:?}", track)
-        );
-    }
-
-    #[test]
-    fn disc_error_new() {
-        let message = "The message";
-        let error = DiscError::new(message);
-
-        assert_eq!(message, error.reason);
+        let mut other = track.clone();
+        assert_eq!(track, other);
+        other.isrc = TrackIsrc::NotPresent;
+        assert_ne!(track, other);
     }
 
     #[test]
-    fn disc_error_fmt() {
-        let error = DiscError::new("The message");
-        assert_eq!("DiscError: The message", format!("{}", error));
-    }
+    fn track_debug() {
+        let track = Track {
+            number: 3,
+            offset: 57402,
+            sectors: 32960,
+            isrc: TrackIsrc::from_raw("DED831801578"),
+        };
 
-    #[test]
-    fn disc_error_debug() {
-        let error = DiscError::new("The message");
         assert_eq!(
-            "DiscError { reason: \"The message\" }",
-            format!("{:?}", error)
+            "Track { number: 3, offset: 57402, sectors: 32960, \
+             isrc: Valid(Isrc(\"DED831801578\")) }",
+            format!("{:?}", track)
         );
     }
 }
@@ -0,0 +1,135 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Helpers shared by the parsers for ripper log files.
+
+use crate::accuraterip::AccurateRipId;
+use crate::toc::{DATA_TRACK_GAP, LEAD_IN};
+use crate::{DiscError, Toc};
+
+/// The largest sector number for which the offsets including lead-in can be calculated.
+const MAX_SECTOR: i32 = i32::MAX - LEAD_IN - 1;
+
+/// A row of the TOC table written by EAC and XLD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TocRow {
+    pub number: i32,
+    pub start: i32,
+    pub end: i32,
+}
+
+/// Parses the first TOC table in a log.
+///
+/// The table has the columns track number, start time, length, start sector and end sector,
+/// separated by `|`. Only the rows are evaluated, so the headers may be localized.
+pub(crate) fn parse_toc_table(log: &str) -> Result<Vec<TocRow>, DiscError> {
+    let mut rows: Vec<TocRow> = Vec::new();
+    for (i, line) in log.lines().enumerate() {
+        match parse_row(line, i + 1)? {
+            Some(row) => {
+                if let Some(previous) = rows.last() {
+                    if Some(row.number) != previous.number.checked_add(1) {
+                        return Err(DiscError::parse(i + 1, "Invalid track number"));
+                    }
+                }
+                rows.push(row);
+            }
+            None if rows.is_empty() || is_separator(line) => {}
+            None => break,
+        }
+    }
+
+    if rows.is_empty() {
+        return Err(DiscError::parse(0, "No TOC found in log"));
+    }
+    Ok(rows)
+}
+
+/// Returns the number of trailing data tracks in `rows`.
+///
+/// The last track is considered to be the data track of an enhanced CD if it starts after a
+/// gap of at least 11400 sectors.
+pub(crate) fn data_tracks(rows: &[TocRow]) -> usize {
+    match rows {
+        [.., audio, data] if data.start - audio.end > DATA_TRACK_GAP => 1,
+        _ => 0,
+    }
+}
+
+/// Builds the TOC from the table rows.
+///
+/// A data track of an enhanced CD is not part of the TOC and the lead-out is placed 11400
+/// sectors before it, the same way libdiscid handles enhanced CDs.
+pub(crate) fn toc_from_rows(rows: &[TocRow]) -> Result<Toc, DiscError> {
+    let audio_rows = &rows[..rows.len() - data_tracks(rows)];
+    let lead_out = if audio_rows.len() < rows.len() {
+        rows[audio_rows.len()].start - DATA_TRACK_GAP + LEAD_IN
+    } else {
+        rows[rows.len() - 1].end + 1 + LEAD_IN
+    };
+
+    let first_track = audio_rows[0].number;
+    let offsets = audio_rows.iter().map(|row| row.start + LEAD_IN).collect();
+    Toc::new(
+        first_track,
+        first_track + audio_rows.len() as i32 - 1,
+        lead_out,
+        offsets,
+    )
+}
+
+/// Calculates the AccurateRip disc ID for the TOC built by [`toc_from_rows`].
+pub(crate) fn accuraterip_id(rows: &[TocRow], toc: &Toc) -> Result<AccurateRipId, DiscError> {
+    if data_tracks(rows) > 0 {
+        AccurateRipId::from_enhanced_cd(toc, rows[rows.len() - 1].end + 1 + LEAD_IN)
+    } else {
+        Ok(AccurateRipId::from_toc(toc))
+    }
+}
+
+/// Parses a row of the TOC table.
+///
+/// Returns `None` if the line is not a table row.
+fn parse_row(line: &str, line_num: usize) -> Result<Option<TocRow>, DiscError> {
+    let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+    if fields.len() != 5 {
+        return Ok(None);
+    }
+    let number = match fields[0].parse::<i32>() {
+        Ok(number) => number,
+        Err(_) => return Ok(None),
+    };
+    let start = parse_sector(fields[3], line_num)?;
+    let end = parse_sector(fields[4], line_num)?;
+    if end < start {
+        return Err(DiscError::parse(line_num, "End sector before start sector"));
+    }
+    Ok(Some(TocRow { number, start, end }))
+}
+
+/// Parses a sector number from a log.
+///
+/// Fails for negative sectors and sectors too large to calculate a TOC from.
+pub(crate) fn parse_sector(value: &str, line_num: usize) -> Result<i32, DiscError> {
+    value
+        .parse::<i32>()
+        .ok()
+        .filter(|sector| (0..=MAX_SECTOR).contains(sector))
+        .ok_or_else(|| DiscError::parse(line_num, &format!("Invalid sector {:?}", value)))
+}
+
+fn is_separator(line: &str) -> bool {
+    line.trim().chars().all(|c| c == '-')
+}
@@ -0,0 +1,86 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Helpers for times given as minutes, seconds and frames (MSF).
+
+/// Number of frames (sectors) per second of audio.
+pub(crate) const FRAMES_PER_SECOND: i32 = 75;
+
+/// Parses a time in the format `MM:SS:FF` and returns the number of frames.
+///
+/// The minutes may have any number of digits. Returns `None` if the time is malformed,
+/// seconds or frames are out of range or the number of frames does not fit into an `i32`.
+pub(crate) fn parse(msf: &str) -> Option<i32> {
+    let mut parts = msf.trim().split(':');
+    let minutes = parse_part(parts.next()?)?;
+    let seconds = parse_part(parts.next()?)?;
+    let frames = parse_part(parts.next()?)?;
+    if parts.next().is_some() || seconds >= 60 || frames >= FRAMES_PER_SECOND {
+        return None;
+    }
+    minutes
+        .checked_mul(60)?
+        .checked_add(seconds)?
+        .checked_mul(FRAMES_PER_SECOND)?
+        .checked_add(frames)
+}
+
+fn parse_part(part: &str) -> Option<i32> {
+    if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    part.parse().ok()
+}
+
+/// Formats a number of frames as `MM:SS:FF`.
+pub(crate) fn format(frames: i32) -> String {
+    let seconds = frames / FRAMES_PER_SECOND;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 60,
+        seconds % 60,
+        frames % FRAMES_PER_SECOND
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format, parse};
+
+    #[test]
+    fn msf_parse() {
+        assert_eq!(Some(0), parse("00:00:00"));
+        assert_eq!(Some(150), parse("00:02:00"));
+        assert_eq!(Some(18751), parse("04:10:01"));
+        assert_eq!(Some(450000), parse("100:00:00"));
+        assert_eq!(None, parse("00:60:00"));
+        assert_eq!(None, parse("00:00:75"));
+        assert_eq!(None, parse("00:00"));
+        assert_eq!(None, parse("00:00:00:00"));
+        assert_eq!(None, parse("00:-1:00"));
+        assert_eq!(None, parse("aa:00:00"));
+        assert_eq!(None, parse("99999999:00:00"));
+        assert_eq!(Some(i32::MAX), parse("477218:35:22"));
+        assert_eq!(None, parse("477218:35:23"));
+    }
+
+    #[test]
+    fn msf_format() {
+        assert_eq!("00:00:00", format(0));
+        assert_eq!("00:02:00", format(150));
+        assert_eq!("04:10:01", format(18751));
+        assert_eq!("100:00:00", format(450000));
+    }
+}
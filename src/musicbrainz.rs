@@ -0,0 +1,1373 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Lookup of disc IDs with the [MusicBrainz web service](https://musicbrainz.org/doc/MusicBrainz_API).
+//!
+//! [`UrlBuilder`] builds the URLs for submitting a disc ID, looking it up with the web
+//! service and seeding a new release with the TOC, for musicbrainz.org as well as the test
+//! and beta servers or self-hosted mirrors.
+//!
+//! [`parse_url`] reverses this and extracts the TOC from such URLs, checking that the disc ID
+//! given in the URL matches the TOC.
+//!
+//! [`MusicBrainzClient`] looks up a disc by its disc ID, optionally falling back to a fuzzy
+//! lookup by TOC, and parses the XML or JSON response into a [`LookupResult`]. The result
+//! is either the disc with the releases it is attached to, a CD stub or, for fuzzy lookups,
+//! a list of releases with a similar TOC.
+//!
+//! This module does not implement HTTP itself. Requests are sent through an
+//! [`HttpTransport`], which can wrap any HTTP library or a local mock server. The client
+//! sets a `User-Agent` identifying the application, as required by MusicBrainz, and waits
+//! between requests to respect the rate limit of one request per second.
+//!
+//! # Examples
+//!
+//! ```
+//! use discid::musicbrainz::{HttpRequest, HttpResponse, LookupOptions, LookupResult};
+//! use discid::musicbrainz::{MusicBrainzClient, ResponseFormat};
+//! use std::io;
+//!
+//! let transport = |request: &HttpRequest| -> io::Result<HttpResponse> {
+//!     assert!(request.url.starts_with("https://musicbrainz.org/ws/2/discid/"));
+//!     assert!(request.url.ends_with("fmt=json"));
+//!     Ok(HttpResponse {
+//!         status: 200,
+//!         body: r#"{"id": "MS0ykA8iqhwhwey1JFwbNd4IIqE-", "sectors": 39738,
+//!                   "offsets": [150, 18901], "releases": []}"#.to_string(),
+//!     })
+//! };
+//!
+//! let mut client = MusicBrainzClient::new(transport, "ExampleApp", "1.0", "me@example.com")
+//!     .with_format(ResponseFormat::Json);
+//! let disc_id = "MS0ykA8iqhwhwey1JFwbNd4IIqE-".parse().expect("invalid disc ID");
+//! let result = client
+//!     .lookup_disc_id(&disc_id, &LookupOptions::default())
+//!     .expect("lookup failed");
+//! match result {
+//!     LookupResult::Disc(disc) => assert_eq!(vec![150, 18901], disc.offsets),
+//!     _ => panic!("disc not found"),
+//! }
+//! ```
+//!
+//! [`UrlBuilder`]: ./struct.UrlBuilder.html
+//! [`parse_url`]: ./fn.parse_url.html
+//! [`MusicBrainzClient`]: ./struct.MusicBrainzClient.html
+//! [`LookupResult`]: ./enum.LookupResult.html
+//! [`HttpTransport`]: ./trait.HttpTransport.html
+
+use crate::json;
+use crate::xml;
+use crate::{DiscError, MusicBrainzDiscId, Toc};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The minimum interval between two requests allowed by MusicBrainz.
+const RATE_LIMIT: Duration = Duration::from_secs(1);
+
+/// An HTTP GET request sent by the [`MusicBrainzClient`].
+///
+/// [`MusicBrainzClient`]: ./struct.MusicBrainzClient.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    /// The complete URL including the query string.
+    pub url: String,
+
+    /// The request headers as name and value, including `User-Agent` and `Accept`.
+    pub headers: Vec<(String, String)>,
+}
+
+/// The response to an [`HttpRequest`].
+///
+/// [`HttpRequest`]: ./struct.HttpRequest.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    /// The HTTP status code.
+    pub status: u16,
+
+    /// The response body.
+    pub body: String,
+}
+
+/// Sends HTTP requests for the [`MusicBrainzClient`].
+///
+/// Implemented for all closures taking an [`HttpRequest`] and returning an [`HttpResponse`].
+///
+/// [`MusicBrainzClient`]: ./struct.MusicBrainzClient.html
+/// [`HttpRequest`]: ./struct.HttpRequest.html
+/// [`HttpResponse`]: ./struct.HttpResponse.html
+pub trait HttpTransport {
+    /// Sends a GET request and returns the response.
+    ///
+    /// Responses with an error status must be returned as `Ok`, an `Err` is only expected if
+    /// no response was received at all.
+    fn get(&mut self, request: &HttpRequest) -> io::Result<HttpResponse>;
+}
+
+impl<F> HttpTransport for F
+where
+    F: FnMut(&HttpRequest) -> io::Result<HttpResponse>,
+{
+    fn get(&mut self, request: &HttpRequest) -> io::Result<HttpResponse> {
+        self(request)
+    }
+}
+
+/// The format of the web service responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResponseFormat {
+    /// MusicBrainz XML metadata, the default format of the web service.
+    Xml,
+
+    /// JSON.
+    Json,
+}
+
+/// Options for a disc ID lookup.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct LookupOptions {
+    /// Additional information to include for the releases, e.g. `artists` or `recordings`.
+    pub includes: Vec<String>,
+
+    /// Whether to return CD stubs, if no disc matches.
+    pub cdstubs: bool,
+
+    /// Whether to include releases on all media formats, not only CDs, in fuzzy lookups.
+    pub all_media_formats: bool,
+}
+
+/// A MusicBrainz server.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum Server {
+    /// The main server at `https://musicbrainz.org`, the default.
+    #[default]
+    Production,
+
+    /// The test server at `https://test.musicbrainz.org`, which uses a separate database for
+    /// testing edits and submissions.
+    Test,
+
+    /// The beta server at `https://beta.musicbrainz.org`, running the next version of the
+    /// software on the production database.
+    Beta,
+
+    /// Any other server, e.g. a self-hosted mirror, given by its base URL like
+    /// `http://localhost:5000`.
+    Custom(String),
+}
+
+impl Server {
+    /// The base URL of the server, without trailing slash.
+    pub fn base_url(&self) -> &str {
+        match self {
+            Server::Production => "https://musicbrainz.org",
+            Server::Test => "https://test.musicbrainz.org",
+            Server::Beta => "https://beta.musicbrainz.org",
+            Server::Custom(url) => url.trim_end_matches('/'),
+        }
+    }
+}
+
+/// Builds MusicBrainz URLs for a disc.
+///
+/// Unlike [`DiscId::submission_url`], which always points to `http://musicbrainz.org`, the
+/// URLs use the configured [`Server`], by default `https://musicbrainz.org`.
+///
+/// # Examples
+///
+/// ```
+/// use discid::musicbrainz::{LookupOptions, ResponseFormat, Server, UrlBuilder};
+/// use discid::Toc;
+///
+/// let toc: Toc = "1 2 39738 150 18901".parse().expect("parsing TOC failed");
+/// let urls = UrlBuilder::new(&toc).server(Server::Test);
+/// assert_eq!(
+///     "https://test.musicbrainz.org/cdtoc/attach?id=MS0ykA8iqhwhwey1JFwbNd4IIqE-&tracks=2&toc=1+2+39738+150+18901",
+///     urls.submission_url()
+/// );
+/// assert_eq!(
+///     "https://test.musicbrainz.org/ws/2/discid/MS0ykA8iqhwhwey1JFwbNd4IIqE-?toc=1+2+39738+150+18901&cdstubs=no&fmt=json",
+///     urls.lookup_url(&LookupOptions::default(), ResponseFormat::Json)
+/// );
+/// assert_eq!(
+///     "https://test.musicbrainz.org/release/add?mediums.0.format=CD&mediums.0.toc=1+2+39738+150+18901",
+///     urls.seeding_url()
+/// );
+/// ```
+///
+/// [`DiscId::submission_url`]: ../struct.DiscId.html#method.submission_url
+/// [`Server`]: ./enum.Server.html
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UrlBuilder {
+    toc: Toc,
+    server: Server,
+}
+
+impl UrlBuilder {
+    /// Creates a builder for the disc with the given TOC.
+    pub fn new(toc: &Toc) -> UrlBuilder {
+        UrlBuilder {
+            toc: toc.clone(),
+            server: Server::default(),
+        }
+    }
+
+    /// Uses another server.
+    pub fn server(mut self, server: Server) -> UrlBuilder {
+        self.server = server;
+        self
+    }
+
+    /// The URL for attaching the disc ID to a release.
+    pub fn submission_url(&self) -> String {
+        format!(
+            "{}/cdtoc/attach?id={}&tracks={}&toc={}",
+            self.server.base_url(),
+            self.toc.id(),
+            self.toc.last_track(),
+            toc_param(&self.toc)
+        )
+    }
+
+    /// The web service URL for looking up the disc.
+    ///
+    /// The TOC is included, so the web service falls back to a fuzzy lookup by TOC if the
+    /// disc ID is not known.
+    pub fn lookup_url(&self, options: &LookupOptions, format: ResponseFormat) -> String {
+        lookup_url(
+            &self.server,
+            &self.toc.id(),
+            Some(&self.toc),
+            options,
+            format,
+        )
+    }
+
+    /// The URL for adding a new release, with a CD medium seeded from the TOC.
+    pub fn seeding_url(&self) -> String {
+        format!(
+            "{}/release/add?mediums.0.format=CD&mediums.0.toc={}",
+            self.server.base_url(),
+            toc_param(&self.toc)
+        )
+    }
+}
+
+/// Extracts the TOC from a MusicBrainz URL.
+///
+/// Supported are submission URLs like `…/cdtoc/attach?id=…&tracks=…&toc=…` and web service
+/// URLs like `…/ws/2/discid/…?toc=…`, as built by [`UrlBuilder`] or libdiscid. The numbers
+/// of the TOC can be separated by `+` or encoded spaces.
+///
+/// The disc ID and the number of tracks given in the URL are optional, but if present they
+/// must match the TOC. Otherwise the URL has been modified or corrupted and an error is
+/// returned. Use [`DiscId::from_toc`] to create a `DiscId` from the TOC, or
+/// [`DiscId::from_url`] to do both at once.
+///
+/// # Examples
+///
+/// ```
+/// use discid::musicbrainz::parse_url;
+///
+/// let toc = parse_url("https://musicbrainz.org/cdtoc/attach?id=MS0ykA8iqhwhwey1JFwbNd4IIqE-&tracks=2&toc=1+2+39738+150+18901")
+///     .expect("parsing URL failed");
+/// assert_eq!("1 2 39738 150 18901", toc.to_string());
+///
+/// let tampered = "https://musicbrainz.org/ws/2/discid/MS0ykA8iqhwhwey1JFwbNd4IIqE-?toc=1+2+39738+150+18902";
+/// assert!(parse_url(tampered).is_err());
+/// ```
+///
+/// [`UrlBuilder`]: ./struct.UrlBuilder.html
+/// [`DiscId::from_toc`]: ../struct.DiscId.html#method.from_toc
+/// [`DiscId::from_url`]: ../struct.DiscId.html#method.from_url
+pub fn parse_url(url: &str) -> Result<Toc, DiscError> {
+    let url = url.trim();
+    let url = url.split('#').next().unwrap_or_default();
+    let (path, query) = match url.find('?') {
+        Some(index) => (&url[..index], &url[index + 1..]),
+        None => (url, ""),
+    };
+
+    let mut disc_id = path
+        .rsplit_once("/discid/")
+        .map(|(_, id)| id.trim_end_matches('/'))
+        .filter(|&id| !id.is_empty() && id != "-")
+        .map(str::to_string);
+    let mut tracks = None;
+    let mut toc = None;
+    for param in query.split('&') {
+        let (name, value) = param.split_once('=').unwrap_or((param, ""));
+        let value = decode_param(value)?;
+        match name {
+            "id" => disc_id = Some(value),
+            "tracks" => {
+                let value = value
+                    .trim()
+                    .parse::<i32>()
+                    .map_err(|_| DiscError::parse(0, "Invalid track count in URL"))?;
+                tracks = Some(value);
+            }
+            "toc" => toc = Some(value.parse::<Toc>()?),
+            _ => {}
+        }
+    }
+
+    let toc = toc.ok_or_else(|| DiscError::parse(0, "No TOC in URL"))?;
+    if tracks.is_some_and(|tracks| tracks != toc.last_track()) {
+        return Err(DiscError::parse(0, "Track count in URL does not match TOC"));
+    }
+    if disc_id.is_some_and(|id| id != toc.id()) {
+        return Err(DiscError::parse(0, "Disc ID in URL does not match TOC"));
+    }
+    Ok(toc)
+}
+
+/// Decodes a value of the query string, where `+` is a space.
+fn decode_param(value: &str) -> Result<String, DiscError> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+    while let Some(byte) = input.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [input.next().unwrap_or(0), input.next().unwrap_or(0)];
+                let decoded = std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| DiscError::parse(0, "Invalid escape sequence in URL"))?;
+                bytes.push(decoded);
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| DiscError::parse(0, "Invalid escape sequence in URL"))
+}
+
+/// An error of a MusicBrainz lookup.
+#[derive(Debug)]
+pub enum MusicBrainzError {
+    /// The transport failed to send the request or to receive the response.
+    Transport(io::Error),
+
+    /// The server returned an error status.
+    Http {
+        /// The HTTP status code.
+        status: u16,
+        /// The error message of the web service, if any.
+        message: String,
+    },
+
+    /// The response could not be parsed.
+    Parse(DiscError),
+}
+
+/// The result of a disc ID lookup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LookupResult {
+    /// The disc ID is known, with the releases it is attached to.
+    Disc(Disc),
+
+    /// The disc ID is not known, but a CD stub exists.
+    CdStub(CdStub),
+
+    /// The disc ID is not known, but releases with a similar TOC were found.
+    Releases(Vec<Release>),
+
+    /// Nothing was found.
+    NotFound,
+}
+
+/// A disc ID known to MusicBrainz.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disc {
+    /// The MusicBrainz disc ID.
+    pub id: String,
+
+    /// The lead-out offset in sectors.
+    pub sectors: i32,
+
+    /// The track offsets in sectors.
+    pub offsets: Vec<i32>,
+
+    /// The releases with a medium having this disc ID.
+    pub releases: Vec<Release>,
+}
+
+/// A MusicBrainz release.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Release {
+    /// The MusicBrainz ID of the release.
+    pub id: String,
+
+    /// The title of the release.
+    pub title: String,
+
+    /// The credited artists, if included.
+    pub artist: Option<String>,
+
+    /// The release status, e.g. `Official`.
+    pub status: Option<String>,
+
+    /// The release date in the format `YYYY`, `YYYY-MM` or `YYYY-MM-DD`.
+    pub date: Option<String>,
+
+    /// The release country as ISO 3166-1 code.
+    pub country: Option<String>,
+
+    /// The barcode of the release.
+    pub barcode: Option<String>,
+
+    /// The media of the release.
+    pub media: Vec<Medium>,
+}
+
+/// A medium of a [`Release`].
+///
+/// [`Release`]: ./struct.Release.html
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Medium {
+    /// The position of the medium in the release.
+    pub position: Option<i32>,
+
+    /// The format of the medium, e.g. `CD`.
+    pub format: Option<String>,
+
+    /// The number of tracks.
+    pub track_count: i32,
+
+    /// The disc IDs attached to the medium.
+    pub disc_ids: Vec<String>,
+}
+
+/// A CD stub, a disc ID with minimal metadata submitted without creating a release.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CdStub {
+    /// The disc ID of the CD stub.
+    pub id: String,
+
+    /// The title of the disc.
+    pub title: String,
+
+    /// The artist of the disc.
+    pub artist: Option<String>,
+
+    /// The barcode of the disc.
+    pub barcode: Option<String>,
+
+    /// A comment to distinguish the disc from similar ones.
+    pub comment: Option<String>,
+
+    /// The tracks of the disc.
+    pub tracks: Vec<CdStubTrack>,
+}
+
+/// A track of a [`CdStub`].
+///
+/// [`CdStub`]: ./struct.CdStub.html
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CdStubTrack {
+    /// The title of the track.
+    pub title: String,
+
+    /// The artist of the track, for discs with various artists.
+    pub artist: Option<String>,
+
+    /// The length of the track in milliseconds.
+    pub length: Option<u32>,
+}
+
+/// A client for disc ID lookups, sending requests through an [`HttpTransport`].
+///
+/// [`HttpTransport`]: ./trait.HttpTransport.html
+#[derive(Debug)]
+pub struct MusicBrainzClient<T> {
+    transport: T,
+    server: Server,
+    user_agent: String,
+    format: ResponseFormat,
+    rate_limit: Duration,
+    last_request: Option<Instant>,
+}
+
+impl<T: HttpTransport> MusicBrainzClient<T> {
+    /// Creates a client for musicbrainz.org using the given transport.
+    ///
+    /// The `User-Agent` is built from the name, version and contact information (an URL or
+    /// email address) of the application as `application/version ( contact )`.
+    pub fn new(
+        transport: T,
+        application: &str,
+        version: &str,
+        contact: &str,
+    ) -> MusicBrainzClient<T> {
+        MusicBrainzClient {
+            transport,
+            server: Server::default(),
+            user_agent: format!("{}/{} ( {} )", application, version, contact),
+            format: ResponseFormat::Xml,
+            rate_limit: RATE_LIMIT,
+            last_request: None,
+        }
+    }
+
+    /// Uses another server, e.g. the test server or a mirror.
+    pub fn with_server(mut self, server: Server) -> MusicBrainzClient<T> {
+        self.server = server;
+        self
+    }
+
+    /// Requests responses in the given format. The default is XML.
+    pub fn with_format(mut self, format: ResponseFormat) -> MusicBrainzClient<T> {
+        self.format = format;
+        self
+    }
+
+    /// Changes the minimum interval between two requests.
+    ///
+    /// The default of one second is required by musicbrainz.org. Only change this for
+    /// mirrors without rate limit or for tests.
+    pub fn with_rate_limit(mut self, interval: Duration) -> MusicBrainzClient<T> {
+        self.rate_limit = interval;
+        self
+    }
+
+    /// The `User-Agent` sent with each request.
+    pub fn user_agent(&self) -> &str {
+        &self.user_agent
+    }
+
+    /// Looks up a disc ID.
+    pub fn lookup_disc_id(
+        &mut self,
+        disc_id: &MusicBrainzDiscId,
+        options: &LookupOptions,
+    ) -> Result<LookupResult, MusicBrainzError> {
+        self.lookup(disc_id, None, options)
+    }
+
+    /// Looks up the disc ID of a TOC.
+    ///
+    /// If the disc ID is not known, MusicBrainz returns the releases with a medium having a
+    /// similar TOC, see [`LookupResult::Releases`].
+    ///
+    /// [`LookupResult::Releases`]: ./enum.LookupResult.html#variant.Releases
+    pub fn lookup_toc(
+        &mut self,
+        toc: &Toc,
+        options: &LookupOptions,
+    ) -> Result<LookupResult, MusicBrainzError> {
+        self.lookup(&MusicBrainzDiscId::from_toc(toc), Some(toc), options)
+    }
+
+    fn lookup(
+        &mut self,
+        disc_id: &MusicBrainzDiscId,
+        toc: Option<&Toc>,
+        options: &LookupOptions,
+    ) -> Result<LookupResult, MusicBrainzError> {
+        let accept = match self.format {
+            ResponseFormat::Xml => "application/xml",
+            ResponseFormat::Json => "application/json",
+        };
+        let request = HttpRequest {
+            url: lookup_url(&self.server, disc_id.as_str(), toc, options, self.format),
+            headers: vec![
+                ("User-Agent".to_string(), self.user_agent.clone()),
+                ("Accept".to_string(), accept.to_string()),
+            ],
+        };
+
+        let response = self.send(&request)?;
+        match response.status {
+            200 => {
+                LookupResult::parse(&response.body, self.format).map_err(MusicBrainzError::Parse)
+            }
+            404 => Ok(LookupResult::NotFound),
+            status => Err(MusicBrainzError::Http {
+                status,
+                message: error_message(&response.body, self.format),
+            }),
+        }
+    }
+
+    /// Sends a request, waiting as required by the rate limit.
+    fn send(&mut self, request: &HttpRequest) -> io::Result<HttpResponse> {
+        if let Some(last_request) = self.last_request {
+            let elapsed = last_request.elapsed();
+            if elapsed < self.rate_limit {
+                thread::sleep(self.rate_limit - elapsed);
+            }
+        }
+        self.last_request = Some(Instant::now());
+        self.transport.get(request)
+    }
+}
+
+impl LookupResult {
+    /// Parses the response to a disc ID lookup in the given format.
+    pub fn parse(response: &str, format: ResponseFormat) -> Result<LookupResult, DiscError> {
+        match format {
+            ResponseFormat::Xml => LookupResult::from_xml(response),
+            ResponseFormat::Json => LookupResult::from_json(response),
+        }
+    }
+
+    /// Parses the XML response to a disc ID lookup.
+    pub fn from_xml(response: &str) -> Result<LookupResult, DiscError> {
+        let root = xml::parse(response)?;
+        if root.name != "metadata" {
+            return Err(DiscError::parse(0, "Not a MusicBrainz metadata response"));
+        }
+        if let Some(disc) = root.child("disc") {
+            let offsets = disc
+                .child("offset-list")
+                .map(|list| {
+                    list.children("offset")
+                        .map(|offset| xml_number(offset, "offset"))
+                        .collect::<Result<_, _>>()
+                })
+                .transpose()?
+                .unwrap_or_default();
+            Ok(LookupResult::Disc(Disc {
+                id: disc.attr("id").unwrap_or_default().to_string(),
+                sectors: disc
+                    .child("sectors")
+                    .map(|sectors| xml_number(sectors, "sectors"))
+                    .transpose()?
+                    .unwrap_or_default(),
+                offsets,
+                releases: xml_releases(disc)?,
+            }))
+        } else if let Some(stub) = root.child("cdstub") {
+            let tracks = stub
+                .child("track-list")
+                .map(|list| {
+                    list.children("track")
+                        .map(|track| {
+                            Ok(CdStubTrack {
+                                title: track.child_text("title").unwrap_or_default(),
+                                artist: xml_text(track, "artist"),
+                                length: track
+                                    .child("length")
+                                    .map(|length| xml_number(length, "length"))
+                                    .transpose()?,
+                            })
+                        })
+                        .collect::<Result<_, DiscError>>()
+                })
+                .transpose()?
+                .unwrap_or_default();
+            Ok(LookupResult::CdStub(CdStub {
+                id: stub.attr("id").unwrap_or_default().to_string(),
+                title: stub.child_text("title").unwrap_or_default(),
+                artist: xml_text(stub, "artist"),
+                barcode: xml_text(stub, "barcode"),
+                comment: xml_text(stub, "comment"),
+                tracks,
+            }))
+        } else if root.child("release-list").is_some() {
+            Ok(LookupResult::Releases(xml_releases(&root)?))
+        } else {
+            Ok(LookupResult::NotFound)
+        }
+    }
+
+    /// Parses the JSON response to a disc ID lookup.
+    pub fn from_json(response: &str) -> Result<LookupResult, DiscError> {
+        let root = json::parse(response)?;
+        if root.get("sectors").is_some() {
+            Ok(LookupResult::Disc(Disc {
+                id: root.str_member("id").unwrap_or_default(),
+                sectors: json_number(&root, "sectors")?.unwrap_or_default(),
+                offsets: root
+                    .get("offsets")
+                    .map_or(&[][..], json::Value::elements)
+                    .iter()
+                    .map(|offset| {
+                        offset
+                            .as_i64()
+                            .and_then(|offset| i32::try_from(offset).ok())
+                    })
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| invalid_value("offsets"))?,
+                releases: json_releases(&root)?,
+            }))
+        } else if root.get("releases").is_some() {
+            Ok(LookupResult::Releases(json_releases(&root)?))
+        } else if root.get("tracks").is_some() {
+            let tracks = root
+                .get("tracks")
+                .map_or(&[][..], json::Value::elements)
+                .iter()
+                .map(|track| {
+                    Ok(CdStubTrack {
+                        title: track.str_member("title").unwrap_or_default(),
+                        artist: track.str_member("artist"),
+                        length: json_number(track, "length")?,
+                    })
+                })
+                .collect::<Result<_, DiscError>>()?;
+            Ok(LookupResult::CdStub(CdStub {
+                id: root.str_member("id").unwrap_or_default(),
+                title: root.str_member("title").unwrap_or_default(),
+                artist: root.str_member("artist"),
+                barcode: root.str_member("barcode"),
+                comment: root
+                    .str_member("comment")
+                    .or_else(|| root.str_member("disambiguation")),
+                tracks,
+            }))
+        } else {
+            Ok(LookupResult::NotFound)
+        }
+    }
+}
+
+impl Disc {
+    /// The TOC of the disc, with the first track numbered 1.
+    pub fn toc(&self) -> Result<Toc, DiscError> {
+        Toc::new(
+            1,
+            self.offsets.len() as i32,
+            self.sectors,
+            self.offsets.clone(),
+        )
+    }
+}
+
+/// Builds the web service URL for a disc ID lookup.
+fn lookup_url(
+    server: &Server,
+    disc_id: &str,
+    toc: Option<&Toc>,
+    options: &LookupOptions,
+    format: ResponseFormat,
+) -> String {
+    let mut params = Vec::new();
+    if let Some(toc) = toc {
+        params.push(format!("toc={}", toc_param(toc)));
+    }
+    if !options.cdstubs {
+        params.push("cdstubs=no".to_string());
+    }
+    if !options.includes.is_empty() {
+        params.push(format!("inc={}", options.includes.join("+")));
+    }
+    if options.all_media_formats {
+        params.push("media-format=all".to_string());
+    }
+    if format == ResponseFormat::Json {
+        params.push("fmt=json".to_string());
+    }
+    let mut url = format!("{}/ws/2/discid/{}", server.base_url(), disc_id);
+    if !params.is_empty() {
+        url.push('?');
+        url.push_str(&params.join("&"));
+    }
+    url
+}
+
+/// The TOC as URL parameter value, with the numbers separated by `+`.
+fn toc_param(toc: &Toc) -> String {
+    toc.to_string().replace(' ', "+")
+}
+
+fn xml_releases(parent: &xml::Element) -> Result<Vec<Release>, DiscError> {
+    let list = match parent.child("release-list") {
+        Some(list) => list,
+        None => return Ok(Vec::new()),
+    };
+    list.children("release")
+        .map(|release| {
+            let media = release
+                .child("medium-list")
+                .map(|list| {
+                    list.children("medium")
+                        .map(|medium| {
+                            Ok(Medium {
+                                position: medium
+                                    .child("position")
+                                    .map(|position| xml_number(position, "position"))
+                                    .transpose()?,
+                                format: xml_text(medium, "format"),
+                                track_count: medium
+                                    .child("track-list")
+                                    .and_then(|tracks| tracks.attr("count"))
+                                    .map(|count| count.parse().map_err(|_| invalid_value("count")))
+                                    .transpose()?
+                                    .unwrap_or_default(),
+                                disc_ids: medium
+                                    .child("disc-list")
+                                    .map(|discs| {
+                                        discs
+                                            .children("disc")
+                                            .filter_map(|disc| disc.attr("id"))
+                                            .map(str::to_string)
+                                            .collect()
+                                    })
+                                    .unwrap_or_default(),
+                            })
+                        })
+                        .collect::<Result<_, DiscError>>()
+                })
+                .transpose()?
+                .unwrap_or_default();
+            let artist = release.child("artist-credit").map(|credit| {
+                credit
+                    .children("name-credit")
+                    .map(|name_credit| {
+                        let name = xml_text(name_credit, "name")
+                            .or_else(|| {
+                                name_credit
+                                    .child("artist")
+                                    .and_then(|artist| artist.child_text("name"))
+                            })
+                            .unwrap_or_default();
+                        name + name_credit.attr("joinphrase").unwrap_or_default()
+                    })
+                    .collect()
+            });
+            Ok(Release {
+                id: release.attr("id").unwrap_or_default().to_string(),
+                title: release.child_text("title").unwrap_or_default(),
+                artist,
+                status: xml_text(release, "status"),
+                date: xml_text(release, "date"),
+                country: xml_text(release, "country"),
+                barcode: xml_text(release, "barcode"),
+                media,
+            })
+        })
+        .collect()
+}
+
+/// The text of the child element `name`, if present and not empty.
+fn xml_text(element: &xml::Element, name: &str) -> Option<String> {
+    element.child_text(name).filter(|text| !text.is_empty())
+}
+
+fn xml_number<N: std::str::FromStr>(element: &xml::Element, name: &str) -> Result<N, DiscError> {
+    element
+        .text()
+        .trim()
+        .parse()
+        .map_err(|_| invalid_value(name))
+}
+
+fn json_releases(parent: &json::Value) -> Result<Vec<Release>, DiscError> {
+    parent
+        .get("releases")
+        .map_or(&[][..], json::Value::elements)
+        .iter()
+        .map(|release| {
+            let media = release
+                .get("media")
+                .map_or(&[][..], json::Value::elements)
+                .iter()
+                .map(|medium| {
+                    Ok(Medium {
+                        position: json_number(medium, "position")?,
+                        format: medium.str_member("format"),
+                        track_count: json_number(medium, "track-count")?.unwrap_or_default(),
+                        disc_ids: medium
+                            .get("discs")
+                            .map_or(&[][..], json::Value::elements)
+                            .iter()
+                            .filter_map(|disc| disc.str_member("id"))
+                            .collect(),
+                    })
+                })
+                .collect::<Result<_, DiscError>>()?;
+            let artist = release.get("artist-credit").map(|credit| {
+                credit
+                    .elements()
+                    .iter()
+                    .map(|name_credit| {
+                        let name = name_credit
+                            .str_member("name")
+                            .or_else(|| {
+                                name_credit
+                                    .get("artist")
+                                    .and_then(|artist| artist.str_member("name"))
+                            })
+                            .unwrap_or_default();
+                        name + name_credit
+                            .get("joinphrase")
+                            .and_then(json::Value::as_str)
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            });
+            Ok(Release {
+                id: release.str_member("id").unwrap_or_default(),
+                title: release.str_member("title").unwrap_or_default(),
+                artist,
+                status: release.str_member("status"),
+                date: release.str_member("date"),
+                country: release.str_member("country"),
+                barcode: release.str_member("barcode"),
+                media,
+            })
+        })
+        .collect()
+}
+
+/// The number member `key` of a JSON object, `None` if it is missing or `null`.
+fn json_number<N: TryFrom<i64>>(value: &json::Value, key: &str) -> Result<Option<N>, DiscError> {
+    match value.get(key) {
+        None | Some(json::Value::Null) => Ok(None),
+        Some(number) => number
+            .as_i64()
+            .and_then(|number| N::try_from(number).ok())
+            .map(Some)
+            .ok_or_else(|| invalid_value(key)),
+    }
+}
+
+fn invalid_value(name: &str) -> DiscError {
+    DiscError::parse(0, &format!("Invalid value for {}", name))
+}
+
+/// Extracts the error message from an error response of the web service.
+fn error_message(body: &str, format: ResponseFormat) -> String {
+    match format {
+        ResponseFormat::Xml => xml::parse(body)
+            .ok()
+            .and_then(|root| root.child_text("text")),
+        ResponseFormat::Json => json::parse(body)
+            .ok()
+            .and_then(|root| root.str_member("error")),
+    }
+    .unwrap_or_default()
+}
+
+impl Error for MusicBrainzError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MusicBrainzError::Transport(err) => Some(err),
+            MusicBrainzError::Parse(err) => Some(err),
+            MusicBrainzError::Http { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for MusicBrainzError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MusicBrainzError::Transport(err) => write!(f, "MusicBrainzError: {}", err),
+            MusicBrainzError::Http { status, message } => {
+                write!(f, "MusicBrainzError: HTTP {} {}", status, message)
+            }
+            MusicBrainzError::Parse(err) => write!(f, "MusicBrainzError: {}", err),
+        }
+    }
+}
+
+impl From<io::Error> for MusicBrainzError {
+    fn from(err: io::Error) -> MusicBrainzError {
+        MusicBrainzError::Transport(err)
+    }
+}
+
+impl From<DiscError> for MusicBrainzError {
+    fn from(err: DiscError) -> MusicBrainzError {
+        MusicBrainzError::Parse(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_url, CdStub, CdStubTrack, HttpRequest, HttpResponse, LookupOptions, LookupResult,
+        Medium, MusicBrainzClient, MusicBrainzError, Release, ResponseFormat, Server, UrlBuilder,
+    };
+    use crate::{DiscError, MusicBrainzDiscId, Toc};
+    use std::io;
+    use std::time::{Duration, Instant};
+
+    const DISC_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<metadata xmlns="http://musicbrainz.org/ns/mmd-2.0#">
+  <disc id="MS0ykA8iqhwhwey1JFwbNd4IIqE-">
+    <sectors>39738</sectors>
+    <offset-list count="2">
+      <offset position="1">150</offset>
+      <offset position="2">18901</offset>
+    </offset-list>
+    <release-list count="1">
+      <release id="a1b2c3d4-0000-0000-0000-000000000001">
+        <title>Album &amp; More</title>
+        <status id="4e304316-386d-3409-af2e-78857eec5cfe">Official</status>
+        <artist-credit>
+          <name-credit joinphrase=" feat. ">
+            <artist id="x"><name>First</name></artist>
+          </name-credit>
+          <name-credit>
+            <name>Credited</name>
+            <artist id="y"><name>Second</name></artist>
+          </name-credit>
+        </artist-credit>
+        <date>2020-05-16</date>
+        <country>DE</country>
+        <barcode></barcode>
+        <medium-list count="1">
+          <medium>
+            <position>1</position>
+            <format>CD</format>
+            <disc-list count="1">
+              <disc id="MS0ykA8iqhwhwey1JFwbNd4IIqE-"/>
+            </disc-list>
+            <track-list count="2"/>
+          </medium>
+        </medium-list>
+      </release>
+    </release-list>
+  </disc>
+</metadata>"#;
+
+    const DISC_JSON: &str = r#"{
+  "id": "MS0ykA8iqhwhwey1JFwbNd4IIqE-",
+  "sectors": 39738,
+  "offset-count": 2,
+  "offsets": [150, 18901],
+  "releases": [{
+    "id": "a1b2c3d4-0000-0000-0000-000000000001",
+    "title": "Album & More",
+    "status": "Official",
+    "artist-credit": [
+      {"name": "First", "joinphrase": " feat. ", "artist": {"name": "First"}},
+      {"name": "Credited", "joinphrase": "", "artist": {"name": "Second"}}
+    ],
+    "date": "2020-05-16",
+    "country": "DE",
+    "barcode": "",
+    "media": [{
+      "position": 1,
+      "format": "CD",
+      "track-count": 2,
+      "discs": [{"id": "MS0ykA8iqhwhwey1JFwbNd4IIqE-", "sectors": 39738}]
+    }]
+  }]
+}"#;
+
+    fn expected_release() -> Release {
+        Release {
+            id: "a1b2c3d4-0000-0000-0000-000000000001".to_string(),
+            title: "Album & More".to_string(),
+            artist: Some("First feat. Credited".to_string()),
+            status: Some("Official".to_string()),
+            date: Some("2020-05-16".to_string()),
+            country: Some("DE".to_string()),
+            barcode: None,
+            media: vec![Medium {
+                position: Some(1),
+                format: Some("CD".to_string()),
+                track_count: 2,
+                disc_ids: vec!["MS0ykA8iqhwhwey1JFwbNd4IIqE-".to_string()],
+            }],
+        }
+    }
+
+    fn expected_cdstub() -> CdStub {
+        CdStub {
+            id: "MS0ykA8iqhwhwey1JFwbNd4IIqE-".to_string(),
+            title: "Stub".to_string(),
+            artist: Some("Artist".to_string()),
+            barcode: None,
+            comment: Some("Promo".to_string()),
+            tracks: vec![
+                CdStubTrack {
+                    title: "One".to_string(),
+                    artist: None,
+                    length: Some(250013),
+                },
+                CdStubTrack {
+                    title: "Two".to_string(),
+                    artist: Some("Guest".to_string()),
+                    length: None,
+                },
+            ],
+        }
+    }
+
+    fn disc_id() -> MusicBrainzDiscId {
+        "MS0ykA8iqhwhwey1JFwbNd4IIqE-".parse().unwrap()
+    }
+
+    fn response(status: u16, body: &str) -> io::Result<HttpResponse> {
+        Ok(HttpResponse {
+            status,
+            body: body.to_string(),
+        })
+    }
+
+    #[test]
+    fn musicbrainz_parse_disc() {
+        for (body, format) in &[
+            (DISC_XML, ResponseFormat::Xml),
+            (DISC_JSON, ResponseFormat::Json),
+        ] {
+            match LookupResult::parse(body, *format).unwrap() {
+                LookupResult::Disc(disc) => {
+                    assert_eq!("MS0ykA8iqhwhwey1JFwbNd4IIqE-", disc.id);
+                    assert_eq!(39738, disc.sectors);
+                    assert_eq!(vec![150, 18901], disc.offsets);
+                    assert_eq!(vec![expected_release()], disc.releases);
+                    assert_eq!(disc.id, disc.toc().unwrap().id());
+                }
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn musicbrainz_parse_cdstub() {
+        let xml = r#"<metadata xmlns="http://musicbrainz.org/ns/mmd-2.0#">
+  <cdstub id="MS0ykA8iqhwhwey1JFwbNd4IIqE-">
+    <title>Stub</title>
+    <artist>Artist</artist>
+    <comment>Promo</comment>
+    <track-list count="2">
+      <track><title>One</title><length>250013</length></track>
+      <track><title>Two</title><artist>Guest</artist></track>
+    </track-list>
+  </cdstub>
+</metadata>"#;
+        let json = r#"{"id": "MS0ykA8iqhwhwey1JFwbNd4IIqE-", "title": "Stub",
+            "artist": "Artist", "barcode": "", "disambiguation": "Promo", "track-count": 2,
+            "tracks": [{"title": "One", "artist": null, "length": 250013},
+                       {"title": "Two", "artist": "Guest", "length": null}]}"#;
+        assert_eq!(
+            LookupResult::CdStub(expected_cdstub()),
+            LookupResult::from_xml(xml).unwrap()
+        );
+        assert_eq!(
+            LookupResult::CdStub(expected_cdstub()),
+            LookupResult::from_json(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn musicbrainz_parse_release_list() {
+        let xml = r#"<metadata><release-list count="1">
+            <release id="a1b2c3d4-0000-0000-0000-000000000002"><title>Similar</title></release>
+            </release-list></metadata>"#;
+        let json = r#"{"release-count": 1, "release-offset": 0, "releases": [
+            {"id": "a1b2c3d4-0000-0000-0000-000000000002", "title": "Similar", "media": []}]}"#;
+        let expected = LookupResult::Releases(vec![Release {
+            id: "a1b2c3d4-0000-0000-0000-000000000002".to_string(),
+            title: "Similar".to_string(),
+            ..Release::default()
+        }]);
+        assert_eq!(expected, LookupResult::from_xml(xml).unwrap());
+        assert_eq!(expected, LookupResult::from_json(json).unwrap());
+    }
+
+    #[test]
+    fn musicbrainz_parse_errors() {
+        assert_eq!(
+            Err(DiscError::parse(0, "Not a MusicBrainz metadata response")),
+            LookupResult::from_xml("<error><text>Not Found</text></error>")
+        );
+        assert_eq!(
+            Err(DiscError::parse(0, "Invalid value for sectors")),
+            LookupResult::from_xml(
+                "<metadata><disc id='x'><sectors>many</sectors></disc></metadata>"
+            )
+        );
+        assert_eq!(
+            Err(DiscError::parse(0, "Invalid value for offsets")),
+            LookupResult::from_json(r#"{"sectors": 1, "offsets": ["150"]}"#)
+        );
+        assert_eq!(
+            Err(DiscError::parse(0, "Invalid value for offsets")),
+            LookupResult::from_json(r#"{"sectors": 1, "offsets": [4294967446]}"#)
+        );
+    }
+
+    #[test]
+    fn musicbrainz_client_lookup() {
+        let toc: Toc = "1 2 39738 150 18901".parse().unwrap();
+        let mut requests = Vec::new();
+        let transport = |request: &HttpRequest| {
+            requests.push(request.clone());
+            response(200, DISC_JSON)
+        };
+        {
+            let mut client = MusicBrainzClient::new(transport, "Test", "1.0", "test@example.com")
+                .with_server(Server::Custom("http://localhost:5000/".to_string()))
+                .with_format(ResponseFormat::Json)
+                .with_rate_limit(Duration::from_millis(0));
+            assert_eq!("Test/1.0 ( test@example.com )", client.user_agent());
+            let options = LookupOptions {
+                includes: vec!["artists".to_string(), "recordings".to_string()],
+                cdstubs: true,
+                all_media_formats: true,
+            };
+            assert!(matches!(
+                client.lookup_toc(&toc, &options),
+                Ok(LookupResult::Disc(_))
+            ));
+            assert!(matches!(
+                client.lookup_disc_id(&disc_id(), &LookupOptions::default()),
+                Ok(LookupResult::Disc(_))
+            ));
+        }
+        assert_eq!(
+            "http://localhost:5000/ws/2/discid/MS0ykA8iqhwhwey1JFwbNd4IIqE-?toc=1+2+39738+150+18901&inc=artists+recordings&media-format=all&fmt=json",
+            requests[0].url
+        );
+        assert_eq!(
+            "http://localhost:5000/ws/2/discid/MS0ykA8iqhwhwey1JFwbNd4IIqE-?cdstubs=no&fmt=json",
+            requests[1].url
+        );
+        assert_eq!(
+            vec![
+                (
+                    "User-Agent".to_string(),
+                    "Test/1.0 ( test@example.com )".to_string()
+                ),
+                ("Accept".to_string(), "application/json".to_string()),
+            ],
+            requests[1].headers
+        );
+    }
+
+    #[test]
+    fn musicbrainz_client_errors() {
+        let mut statuses = vec![404, 503].into_iter();
+        let transport = move |_: &HttpRequest| match statuses.next() {
+            Some(404) => response(404, "<error><text>Not Found</text></error>"),
+            Some(status) => response(status, "<error><text>Rate limit exceeded</text></error>"),
+            None => Err(io::Error::new(io::ErrorKind::TimedOut, "timeout")),
+        };
+        let mut client = MusicBrainzClient::new(transport, "Test", "1.0", "test@example.com")
+            .with_rate_limit(Duration::from_millis(0));
+        let options = LookupOptions::default();
+        assert!(matches!(
+            client.lookup_disc_id(&disc_id(), &options),
+            Ok(LookupResult::NotFound)
+        ));
+        match client.lookup_disc_id(&disc_id(), &options) {
+            Err(MusicBrainzError::Http { status, message }) => {
+                assert_eq!(503, status);
+                assert_eq!("Rate limit exceeded", message);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(matches!(
+            client.lookup_disc_id(&disc_id(), &options),
+            Err(MusicBrainzError::Transport(_))
+        ));
+    }
+
+    #[test]
+    fn musicbrainz_client_rate_limit() {
+        let transport = |_: &HttpRequest| response(200, "<metadata/>");
+        let mut client = MusicBrainzClient::new(transport, "Test", "1.0", "test@example.com")
+            .with_rate_limit(Duration::from_millis(100));
+        let options = LookupOptions::default();
+        let start = Instant::now();
+        client.lookup_disc_id(&disc_id(), &options).unwrap();
+        client.lookup_disc_id(&disc_id(), &options).unwrap();
+        client.lookup_disc_id(&disc_id(), &options).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn musicbrainz_url_builder() {
+        let toc: Toc = "1 10 206535 150 18901 39738 59557 79152 100126 124833 147278 166336 182560"
+            .parse()
+            .unwrap();
+        let urls = UrlBuilder::new(&toc);
+        assert_eq!(
+            "https://musicbrainz.org/cdtoc/attach?id=Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-&tracks=10&toc=1+10+206535+150+18901+39738+59557+79152+100126+124833+147278+166336+182560",
+            urls.submission_url()
+        );
+        assert_eq!(
+            "https://musicbrainz.org/ws/2/discid/Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-?toc=1+10+206535+150+18901+39738+59557+79152+100126+124833+147278+166336+182560&inc=artists",
+            urls.lookup_url(
+                &LookupOptions {
+                    includes: vec!["artists".to_string()],
+                    cdstubs: true,
+                    all_media_formats: false,
+                },
+                ResponseFormat::Xml
+            )
+        );
+
+        let urls = urls.server(Server::Beta);
+        assert!(urls
+            .seeding_url()
+            .starts_with("https://beta.musicbrainz.org/release/add?"));
+        let urls = urls.server(Server::Custom("http://localhost:5000/".to_string()));
+        assert!(urls
+            .submission_url()
+            .starts_with("http://localhost:5000/cdtoc/attach?"));
+    }
+
+    #[test]
+    fn musicbrainz_parse_url() {
+        let toc: Toc = "1 10 206535 150 18901 39738 59557 79152 100126 124833 147278 166336 182560"
+            .parse()
+            .unwrap();
+        let urls = UrlBuilder::new(&toc);
+        assert_eq!(Ok(toc.clone()), parse_url(&urls.submission_url()));
+        assert_eq!(Ok(toc.clone()), parse_url(&toc.submission_url()));
+        assert_eq!(
+            Ok(toc.clone()),
+            parse_url(&urls.lookup_url(&LookupOptions::default(), ResponseFormat::Json))
+        );
+        assert_eq!(
+            Ok(toc.clone()),
+            parse_url(&urls.seeding_url().replace("mediums.0.toc", "toc"))
+        );
+        assert_eq!(
+            Ok(toc.clone()),
+            parse_url(
+                "https://musicbrainz.org/ws/2/discid/-?toc=1%2010%20206535%20150%2018901%2039738%2059557%2079152%20100126%20124833%20147278%20166336%20182560#top"
+            )
+        );
+    }
+
+    #[test]
+    fn musicbrainz_parse_url_errors() {
+        let url = "http://musicbrainz.org/cdtoc/attach?id=MS0ykA8iqhwhwey1JFwbNd4IIqE-&tracks=2&toc=1+2+39738+150+18901";
+        assert!(parse_url(url).is_ok());
+        assert_eq!(
+            Err(DiscError::parse(0, "Disc ID in URL does not match TOC")),
+            parse_url(&url.replace("18901", "18902"))
+        );
+        assert_eq!(
+            Err(DiscError::parse(0, "Track count in URL does not match TOC")),
+            parse_url(&url.replace("tracks=2", "tracks=3"))
+        );
+        assert_eq!(
+            Err(DiscError::parse(0, "Invalid track count in URL")),
+            parse_url(&url.replace("tracks=2", "tracks=two"))
+        );
+        assert_eq!(
+            Err(DiscError::parse(0, "No TOC in URL")),
+            parse_url("https://musicbrainz.org/cdtoc/MS0ykA8iqhwhwey1JFwbNd4IIqE-")
+        );
+        assert_eq!(
+            Err(DiscError::parse(0, "Invalid escape sequence in URL")),
+            parse_url("https://musicbrainz.org/ws/2/discid/-?toc=1%2")
+        );
+        assert!(parse_url("https://musicbrainz.org/ws/2/discid/-?toc=1+2+39738+150").is_err());
+    }
+}
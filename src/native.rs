@@ -0,0 +1,319 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Pure Rust implementation of the disc ID calculation.
+//!
+//! The functions in this module do not require libdiscid and are available even if the crate
+//! is built without the default `libdiscid` feature. They accept the TOC in the same layout as
+//! [`DiscId::put`] and return exactly the same values as the corresponding [`DiscId`] methods.
+//!
+//! See also [`Toc`], which provides the same calculations for an owned, validated TOC.
+//!
+//! [`DiscId`]: ../struct.DiscId.html
+//! [`Toc`]: ../struct.Toc.html
+//! [`DiscId::put`]: ../struct.DiscId.html#method.put
+
+use crate::{DiscError, TocErrorKind};
+use sha1_smol::Sha1;
+
+/// The highest possible track number.
+const MAX_TRACK_NUM: i32 = 99;
+
+/// The maximum disc length in sectors accepted by libdiscid (90 minutes).
+const MAX_DISC_LENGTH: i32 = 90 * 60 * 75;
+
+/// Base64 alphabet used by MusicBrainz. It differs from the standard alphabet by using
+/// `.`, `_` and `-` instead of `+`, `/` and `=`, so the result can be used in URLs.
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789._";
+const BASE64_PADDING: char = '-';
+
+/// Calculates the MusicBrainz disc ID for the given TOC.
+///
+/// `first` and `offsets` have the same meaning as for [`DiscId::put`]: `offsets[0]` is the
+/// lead-out, followed by the start sectors of each track.
+///
+/// # Examples
+///
+/// ```
+/// let offsets = [
+///    242457, 150, 44942, 61305, 72755, 96360, 130485, 147315, 164275, 190702, 205412, 220437,
+/// ];
+/// let id = discid::native::id(1, &offsets).expect("calculating disc ID failed");
+/// assert_eq!("lSOVc5h6IXSuzcamJS1Gp4_tRuA-", id);
+/// ```
+///
+/// [`DiscId::put`]: ../struct.DiscId.html#method.put
+pub fn id(first: i32, offsets: &[i32]) -> Result<String, DiscError> {
+    let (last, full_offsets) = full_offsets(first, offsets)?;
+    Ok(calculate_id(first, last, &full_offsets))
+}
+
+/// Calculates the FreeDB disc ID for the given TOC.
+///
+/// `first` and `offsets` have the same meaning as for [`DiscId::put`]. The result is
+/// the same 8 digit hexadecimal string returned by [`DiscId::freedb_id`].
+///
+/// # Examples
+///
+/// ```
+/// let offsets = [
+///    242457, 150, 44942, 61305, 72755, 96360, 130485, 147315, 164275, 190702, 205412, 220437,
+/// ];
+/// let id = discid::native::freedb_id(1, &offsets).expect("calculating FreeDB ID failed");
+/// assert_eq!("b40c9e0b", id);
+/// ```
+///
+/// [`DiscId::put`]: ../struct.DiscId.html#method.put
+/// [`DiscId::freedb_id`]: ../struct.DiscId.html#method.freedb_id
+pub fn freedb_id(first: i32, offsets: &[i32]) -> Result<String, DiscError> {
+    let (last, full_offsets) = full_offsets(first, offsets)?;
+    Ok(calculate_freedb_id(first, last, &full_offsets))
+}
+
+/// Expands `offsets` to the 100 element array used by libdiscid, where the offset of each
+/// track is stored at the index of its track number.
+///
+/// Returns the last track number together with the expanded array. Fails with the same
+/// errors `discid_put` would report for the given TOC.
+pub(crate) fn full_offsets(first: i32, offsets: &[i32]) -> Result<(i32, [i32; 100]), DiscError> {
+    let last = first.saturating_add(offsets.len() as i32).saturating_sub(2);
+    let valid_range = 1..=MAX_TRACK_NUM;
+    if !valid_range.contains(&first) || !valid_range.contains(&last) || first > last {
+        return Err(DiscError::invalid_toc(
+            TocErrorKind::IllegalTrackLimits,
+            "Illegal track limits",
+        ));
+    }
+
+    let lead_out = offsets[0];
+    if lead_out > MAX_DISC_LENGTH {
+        return Err(DiscError::invalid_toc(
+            TocErrorKind::DiscTooLong,
+            "Disc too long",
+        ));
+    }
+
+    let mut full_offsets = [0; 100];
+    full_offsets[0] = lead_out;
+    let mut previous = 0;
+    for (i, &offset) in offsets[1..].iter().enumerate() {
+        if offset < 0 || offset > lead_out {
+            return Err(DiscError::invalid_toc(
+                TocErrorKind::InvalidOffset,
+                "Invalid offset",
+            ));
+        }
+        if offset < previous {
+            return Err(DiscError::invalid_toc(
+                TocErrorKind::NonMonotonicOffsets,
+                "Invalid offset",
+            ));
+        }
+        full_offsets[first as usize + i] = offset;
+        previous = offset;
+    }
+
+    Ok((last, full_offsets))
+}
+
+/// Calculates the MusicBrainz disc ID from an already validated, expanded offset array.
+pub(crate) fn calculate_id(first: i32, last: i32, full_offsets: &[i32; 100]) -> String {
+    let mut sha = Sha1::new();
+    sha.update(format!("{:02X}{:02X}", first, last).as_bytes());
+    for offset in full_offsets.iter() {
+        sha.update(format!("{:08X}", offset).as_bytes());
+    }
+    base64(&sha.digest().bytes())
+}
+
+/// Calculates the FreeDB disc ID from an already validated, expanded offset array.
+pub(crate) fn calculate_freedb_id(first: i32, last: i32, full_offsets: &[i32; 100]) -> String {
+    format!("{:08x}", freedb_id_value(first, last, full_offsets))
+}
+
+/// Calculates the numeric value of the FreeDB disc ID.
+///
+/// The ID consists of the sum of the digits of each track's start time in seconds modulo 255,
+/// the total playing time in seconds and the number of tracks.
+pub(crate) fn freedb_id_value(first: i32, last: i32, full_offsets: &[i32; 100]) -> u32 {
+    let checksum: i32 = full_offsets[first as usize..=last as usize]
+        .iter()
+        .map(|offset| digit_sum(offset / 75))
+        .sum();
+    let length = full_offsets[0] / 75 - full_offsets[first as usize] / 75;
+    let track_count = last - first + 1;
+    ((checksum % 0xff) << 24 | length << 8 | track_count) as u32
+}
+
+fn digit_sum(mut n: i32) -> i32 {
+    let mut sum = 0;
+    while n > 0 {
+        sum += n % 10;
+        n /= 10;
+    }
+    sum
+}
+
+pub(crate) fn base64(data: &[u8]) -> String {
+    let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (n >> (18 - 6 * i)) & 0x3f;
+                result.push(char::from(BASE64_ALPHABET[index as usize]));
+            } else {
+                result.push(BASE64_PADDING);
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{base64, digit_sum, freedb_id, id};
+    use crate::{DiscError, TocErrorKind};
+
+    #[test]
+    fn native_id() {
+        let offsets = [
+            206535, 150, 18901, 39738, 59557, 79152, 100126, 124833, 147278, 166336, 182560,
+        ];
+        let id = id(1, &offsets).expect("id failed");
+        assert_eq!("Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-", id);
+    }
+
+    #[test]
+    fn native_id_first_track_not_one() {
+        let offsets = [
+            206535, 150, 18901, 39738, 59557, 79152, 100126, 124833, 147278, 166336, 182560,
+        ];
+        let id = id(3, &offsets).expect("id failed");
+        assert_eq!("ByBKvJM1hBL7XtvsPyYtIjlX0Bw-", id);
+    }
+
+    #[test]
+    fn native_id_minimal() {
+        let id = id(1, &[44942, 150]).expect("id failed");
+        assert_eq!("ANJa4DGYN_ktpzOwvVPtcjwP7mE-", id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Illegal track limits")]
+    fn native_id_too_many_offsets() {
+        let offsets: [i32; 101] = [0; 101];
+        id(1, &offsets).expect("id failed");
+    }
+
+    #[test]
+    #[should_panic(expected = "Illegal track limits")]
+    fn native_id_no_offsets() {
+        id(1, &[]).expect("id failed");
+    }
+
+    #[test]
+    #[should_panic(expected = "Illegal track limits")]
+    fn native_id_invalid_first_track() {
+        id(i32::MAX, &[2000, 150]).expect("id failed");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid offset")]
+    fn native_id_offsets_not_ascending() {
+        id(1, &[2000, 150, 1000, 500]).expect("id failed");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid offset")]
+    fn native_id_offset_after_lead_out() {
+        id(1, &[2000, 150, 3000]).expect("id failed");
+    }
+
+    #[test]
+    fn native_id_error_kinds() {
+        let kind = |first, offsets: &[i32]| match id(first, offsets) {
+            Err(DiscError::InvalidToc { kind, .. }) => kind,
+            result => panic!("unexpected result {:?}", result),
+        };
+        assert_eq!(TocErrorKind::IllegalTrackLimits, kind(0, &[2000, 150]));
+        assert_eq!(TocErrorKind::InvalidOffset, kind(1, &[2000, -150]));
+        assert_eq!(TocErrorKind::InvalidOffset, kind(1, &[2000, 150, 3000]));
+        assert_eq!(
+            TocErrorKind::NonMonotonicOffsets,
+            kind(1, &[2000, 150, 1000, 500])
+        );
+        assert_eq!(TocErrorKind::DiscTooLong, kind(1, &[405001, 150]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Disc too long")]
+    fn native_id_disc_too_long() {
+        id(1, &[405001, 150]).expect("id failed");
+    }
+
+    #[test]
+    fn native_freedb_id() {
+        let offsets = [
+            206535, 150, 18901, 39738, 59557, 79152, 100126, 124833, 147278, 166336, 182560,
+        ];
+        let id = freedb_id(1, &offsets).expect("freedb_id failed");
+        assert_eq!("830abf0a", id);
+    }
+
+    #[test]
+    fn native_freedb_id_first_track_not_one() {
+        let offsets = [
+            206535, 150, 18901, 39738, 59557, 79152, 100126, 124833, 147278, 166336, 182560,
+        ];
+        let id = freedb_id(3, &offsets).expect("freedb_id failed");
+        assert_eq!("830abf0a", id);
+    }
+
+    #[test]
+    fn native_freedb_id_minimal() {
+        let id = freedb_id(1, &[44942, 150]).expect("freedb_id failed");
+        assert_eq!("02025501", id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Illegal track limits")]
+    fn native_freedb_id_too_many_offsets() {
+        let offsets: [i32; 101] = [0; 101];
+        freedb_id(1, &offsets).expect("freedb_id failed");
+    }
+
+    #[test]
+    fn native_digit_sum() {
+        assert_eq!(0, digit_sum(0));
+        assert_eq!(2, digit_sum(2));
+        assert_eq!(18, digit_sum(2754));
+    }
+
+    #[test]
+    fn native_base64() {
+        assert_eq!("", base64(b""));
+        assert_eq!("Zg--", base64(b"f"));
+        assert_eq!("Zm8-", base64(b"fo"));
+        assert_eq!("Zm9v", base64(b"foo"));
+        assert_eq!("_..A", base64(&[0xff, 0xef, 0x80]));
+    }
+}
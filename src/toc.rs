@@ -0,0 +1,559 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::accuraterip::AccurateRipId;
+use crate::ctdb;
+use crate::native;
+use crate::{DiscError, TocErrorKind};
+use std::fmt;
+use std::str::FromStr;
+
+/// Length of the lead-in in sectors. The first track of a disc starts after the lead-in.
+pub(crate) const LEAD_IN: i32 = 150;
+
+/// Gap in sectors between the end of the audio session and the start of the data session
+/// of an enhanced CD.
+pub(crate) const DATA_TRACK_GAP: i32 = 11400;
+
+/// The table of contents (TOC) of a disc.
+///
+/// A `Toc` holds the first and last track number, the lead-out offset and the start offset
+/// of each track. All values are given in sectors. A `Toc` can only be created through its
+/// checked constructors, so it is always valid and the disc IDs can be calculated without
+/// libdiscid.
+///
+/// The `Display` and `FromStr` implementations use the same format as
+/// [`DiscId::toc_string`] and [`DiscId::parse`].
+///
+/// # Examples
+///
+/// ```
+/// use discid::Toc;
+///
+/// let toc: Toc = "1 11 242457 150 44942 61305 72755 96360 130485 147315 164275 190702 205412 220437"
+///     .parse()
+///     .expect("parsing TOC failed");
+/// assert_eq!(11, toc.last_track());
+/// assert_eq!(242457, toc.lead_out());
+/// assert_eq!("lSOVc5h6IXSuzcamJS1Gp4_tRuA-", toc.id());
+/// ```
+///
+/// [`DiscId::toc_string`]: ./struct.DiscId.html#method.toc_string
+/// [`DiscId::parse`]: ./struct.DiscId.html#method.parse
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "RawToc", into = "RawToc")
+)]
+pub struct Toc {
+    first_track: i32,
+    last_track: i32,
+    lead_out: i32,
+    offsets: Vec<i32>,
+}
+
+impl Toc {
+    /// Creates a TOC from the first and last track number, the lead-out and the start
+    /// offsets of each track.
+    ///
+    /// `offsets` must contain exactly one offset per track, in ascending order. The offsets
+    /// must not be larger than `lead_out`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use discid::Toc;
+    ///
+    /// let toc = Toc::new(1, 3, 2000, vec![150, 700, 1200]).expect("invalid TOC");
+    /// assert_eq!(3, toc.track_count());
+    /// assert_eq!("1 3 2000 150 700 1200", toc.to_string());
+    /// ```
+    pub fn new(
+        first_track: i32,
+        last_track: i32,
+        lead_out: i32,
+        offsets: Vec<i32>,
+    ) -> Result<Toc, DiscError> {
+        if !valid_track_limits(first_track, last_track) {
+            return Err(illegal_track_limits());
+        }
+        let track_count = last_track - first_track + 1;
+        if track_count != offsets.len() as i32 {
+            return Err(track_count_mismatch(offsets.len() as i32, track_count));
+        }
+
+        let mut put_offsets = Vec::with_capacity(offsets.len() + 1);
+        put_offsets.push(lead_out);
+        put_offsets.extend_from_slice(&offsets);
+        native::full_offsets(first_track, &put_offsets)?;
+
+        Ok(Toc {
+            first_track,
+            last_track,
+            lead_out,
+            offsets,
+        })
+    }
+
+    /// Creates a TOC from offsets in the layout used by [`DiscId::put`].
+    ///
+    /// `offsets[0]` is the lead-out, followed by the start offsets of each track.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use discid::Toc;
+    ///
+    /// let toc = Toc::from_offsets(1, &[2000, 150, 700, 1200]).expect("invalid TOC");
+    /// assert_eq!(2000, toc.lead_out());
+    /// assert_eq!(&[150, 700, 1200], toc.offsets());
+    /// ```
+    ///
+    /// [`DiscId::put`]: ./struct.DiscId.html#method.put
+    pub fn from_offsets(first_track: i32, offsets: &[i32]) -> Result<Toc, DiscError> {
+        let (last_track, _) = native::full_offsets(first_track, offsets)?;
+        Ok(Toc {
+            first_track,
+            last_track,
+            lead_out: offsets[0],
+            offsets: offsets[1..].to_vec(),
+        })
+    }
+
+    /// Creates a TOC from data that has already been validated by libdiscid.
+    #[cfg(feature = "libdiscid")]
+    pub(crate) fn new_unchecked(
+        first_track: i32,
+        last_track: i32,
+        lead_out: i32,
+        offsets: Vec<i32>,
+    ) -> Toc {
+        Toc {
+            first_track,
+            last_track,
+            lead_out,
+            offsets,
+        }
+    }
+
+    /// The number of the first track on this disc.
+    pub fn first_track(&self) -> i32 {
+        self.first_track
+    }
+
+    /// The number of the last track on this disc.
+    pub fn last_track(&self) -> i32 {
+        self.last_track
+    }
+
+    /// The number of tracks on this disc.
+    pub fn track_count(&self) -> i32 {
+        self.last_track - self.first_track + 1
+    }
+
+    /// The offset of the lead-out, which equals the length of the disc in sectors.
+    pub fn lead_out(&self) -> i32 {
+        self.lead_out
+    }
+
+    /// The start offsets of all tracks, starting with the first track.
+    pub fn offsets(&self) -> &[i32] {
+        &self.offsets
+    }
+
+    /// The start offset of the track with the given number, if it exists.
+    pub fn track_offset(&self, number: i32) -> Option<i32> {
+        if number < self.first_track || number > self.last_track {
+            return None;
+        }
+        Some(self.offsets[(number - self.first_track) as usize])
+    }
+
+    /// The length in sectors of the track with the given number, if it exists.
+    pub fn track_sectors(&self, number: i32) -> Option<i32> {
+        let offset = self.track_offset(number)?;
+        let next = self.track_offset(number + 1).unwrap_or(self.lead_out);
+        Some(next - offset)
+    }
+
+    /// Returns the lead-out followed by the track offsets, as expected by [`DiscId::put`].
+    ///
+    /// [`DiscId::put`]: ./struct.DiscId.html#method.put
+    pub fn offsets_with_lead_out(&self) -> Vec<i32> {
+        let mut offsets = Vec::with_capacity(self.offsets.len() + 1);
+        offsets.push(self.lead_out);
+        offsets.extend_from_slice(&self.offsets);
+        offsets
+    }
+
+    /// The MusicBrainz disc ID for this TOC.
+    pub fn id(&self) -> String {
+        native::calculate_id(self.first_track, self.last_track, &self.full_offsets())
+    }
+
+    /// The FreeDB disc ID for this TOC.
+    pub fn freedb_id(&self) -> String {
+        native::calculate_freedb_id(self.first_track, self.last_track, &self.full_offsets())
+    }
+
+    /// The AccurateRip disc ID for this TOC.
+    ///
+    /// See [`AccurateRipId::from_toc`] for details.
+    ///
+    /// [`AccurateRipId::from_toc`]: ./accuraterip/struct.AccurateRipId.html#method.from_toc
+    pub fn accuraterip_id(&self) -> AccurateRipId {
+        AccurateRipId::from_toc(self)
+    }
+
+    /// The CUETools Database TOC ID for this TOC.
+    ///
+    /// See [`ctdb::toc_id`] for details.
+    ///
+    /// [`ctdb::toc_id`]: ./ctdb/fn.toc_id.html
+    pub fn ctdb_toc_id(&self) -> String {
+        ctdb::toc_id(self)
+    }
+
+    /// An URL for submitting the disc ID to MusicBrainz.
+    ///
+    /// This is the same URL as returned by [`DiscId::submission_url`]. Use [`UrlBuilder`] for
+    /// HTTPS URLs and other servers.
+    ///
+    /// [`DiscId::submission_url`]: ./struct.DiscId.html#method.submission_url
+    /// [`UrlBuilder`]: ./musicbrainz/struct.UrlBuilder.html
+    pub fn submission_url(&self) -> String {
+        format!(
+            "http://musicbrainz.org/cdtoc/attach?id={}&tracks={}&toc={}",
+            self.id(),
+            self.last_track,
+            self.to_string().replace(' ', "+")
+        )
+    }
+
+    pub(crate) fn full_offsets(&self) -> [i32; 100] {
+        let mut full_offsets = [0; 100];
+        full_offsets[0] = self.lead_out;
+        let first = self.first_track as usize;
+        full_offsets[first..first + self.offsets.len()].copy_from_slice(&self.offsets);
+        full_offsets
+    }
+}
+
+impl fmt::Display for Toc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.first_track, self.last_track, self.lead_out
+        )?;
+        for offset in self.offsets.iter() {
+            write!(f, " {}", offset)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Toc {
+    type Err = DiscError;
+
+    /// Parses a TOC string in the format returned by [`DiscId::toc_string`].
+    ///
+    /// [`DiscId::toc_string`]: ./struct.DiscId.html#method.toc_string
+    fn from_str(toc: &str) -> Result<Toc, DiscError> {
+        let mut i: usize = 0;
+        let mut first_track = 1;
+        let mut last_track = 1;
+        let mut offsets = Vec::new();
+        for part in toc.split(' ') {
+            let parsed_int = part
+                .parse::<i32>()
+                .map_err(|err| DiscError::parse_int(i, err))?;
+            if i == 0 {
+                first_track = parsed_int;
+            } else if i == 1 {
+                last_track = parsed_int;
+            } else {
+                // A last track above 99 is reported as too many offsets below.
+                if i == 2 && (!(1..=99).contains(&first_track) || last_track < first_track) {
+                    return Err(illegal_track_limits());
+                }
+                if i > (last_track as usize + 2) || i > 99 + 2 {
+                    return Err(DiscError::invalid_toc(
+                        TocErrorKind::TooManyOffsets,
+                        "TOC string contains too many offsets (max. 100)",
+                    ));
+                }
+
+                offsets.push(parsed_int);
+            }
+
+            i += 1;
+        }
+
+        if i < 3 {
+            return Err(DiscError::invalid_toc(
+                TocErrorKind::Malformed,
+                &format!("Invalid TOC string {:?}", toc),
+            ));
+        }
+
+        let offset_count = (i - 3) as i32;
+        let track_count = last_track - first_track + 1;
+        if track_count != offset_count {
+            return Err(track_count_mismatch(offset_count, track_count));
+        }
+
+        Toc::from_offsets(first_track, &offsets)
+    }
+}
+
+/// Unvalidated representation of a [`Toc`] used for serialization.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RawToc {
+    first_track: i32,
+    last_track: i32,
+    lead_out: i32,
+    offsets: Vec<i32>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Toc> for RawToc {
+    fn from(toc: Toc) -> RawToc {
+        RawToc {
+            first_track: toc.first_track,
+            last_track: toc.last_track,
+            lead_out: toc.lead_out,
+            offsets: toc.offsets,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<RawToc> for Toc {
+    type Error = DiscError;
+
+    fn try_from(raw: RawToc) -> Result<Toc, DiscError> {
+        Toc::new(raw.first_track, raw.last_track, raw.lead_out, raw.offsets)
+    }
+}
+
+fn valid_track_limits(first_track: i32, last_track: i32) -> bool {
+    (1..=99).contains(&first_track) && (1..=99).contains(&last_track) && first_track <= last_track
+}
+
+fn illegal_track_limits() -> DiscError {
+    DiscError::invalid_toc(TocErrorKind::IllegalTrackLimits, "Illegal track limits")
+}
+
+fn track_count_mismatch(offsets: i32, tracks: i32) -> DiscError {
+    DiscError::invalid_toc(
+        TocErrorKind::TrackCountMismatch { offsets, tracks },
+        &format!(
+            "Number of offsets {} does not match track count {}",
+            offsets, tracks
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Toc;
+    use crate::{DiscError, TocErrorKind};
+
+    #[test]
+    fn toc_new() {
+        let toc = Toc::new(
+            1,
+            10,
+            206535,
+            vec![
+                150, 18901, 39738, 59557, 79152, 100126, 124833, 147278, 166336, 182560,
+            ],
+        )
+        .expect("Toc::new failed");
+        assert_eq!(1, toc.first_track());
+        assert_eq!(10, toc.last_track());
+        assert_eq!(10, toc.track_count());
+        assert_eq!(206535, toc.lead_out());
+        assert_eq!("Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-", toc.id());
+        assert_eq!("830abf0a", toc.freedb_id());
+    }
+
+    #[test]
+    #[should_panic(expected = "Number of offsets 2 does not match track count 3")]
+    fn toc_new_track_count_mismatch() {
+        Toc::new(1, 3, 2000, vec![150, 1000]).expect("Toc::new failed");
+    }
+
+    #[test]
+    #[should_panic(expected = "Illegal track limits")]
+    fn toc_new_illegal_track_limits() {
+        Toc::new(3, 1, 2000, vec![150]).expect("Toc::new failed");
+    }
+
+    #[test]
+    #[should_panic(expected = "Illegal track limits")]
+    fn toc_new_track_limits_overflow() {
+        Toc::new(i32::MIN, 0, 100, vec![150]).expect("Toc::new failed");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid offset")]
+    fn toc_new_offsets_not_ascending() {
+        Toc::new(1, 2, 2000, vec![1000, 150]).expect("Toc::new failed");
+    }
+
+    #[test]
+    fn toc_from_offsets_first_track_not_one() {
+        let offsets = [
+            206535, 150, 18901, 39738, 59557, 79152, 100126, 124833, 147278, 166336, 182560,
+        ];
+        let toc = Toc::from_offsets(3, &offsets).expect("Toc::from_offsets failed");
+        assert_eq!(3, toc.first_track());
+        assert_eq!(12, toc.last_track());
+        assert_eq!("ByBKvJM1hBL7XtvsPyYtIjlX0Bw-", toc.id());
+        assert_eq!(&offsets[..], &toc.offsets_with_lead_out()[..]);
+    }
+
+    #[test]
+    fn toc_submission_url() {
+        let toc: Toc = "1 10 206535 150 18901 39738 59557 79152 100126 124833 147278 166336 182560"
+            .parse()
+            .expect("parsing TOC failed");
+        assert_eq!(
+            "http://musicbrainz.org/cdtoc/attach?id=Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-&tracks=10&toc=1+10+206535+150+18901+39738+59557+79152+100126+124833+147278+166336+182560",
+            toc.submission_url());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn toc_serde() {
+        let toc: Toc = "1 2 2000 150 1000".parse().expect("parsing TOC failed");
+        let json = serde_json::to_string(&toc).expect("serializing failed");
+        assert_eq!(
+            r#"{"first_track":1,"last_track":2,"lead_out":2000,"offsets":[150,1000]}"#,
+            json
+        );
+        let deserialized: Toc = serde_json::from_str(&json).expect("deserializing failed");
+        assert_eq!(toc, deserialized);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn toc_serde_invalid() {
+        let json = r#"{"first_track":1,"last_track":2,"lead_out":2000,"offsets":[1000,150]}"#;
+        let error = serde_json::from_str::<Toc>(json).unwrap_err();
+        assert!(error.to_string().contains("Invalid offset"));
+    }
+
+    #[test]
+    fn toc_track_offset_and_sectors() {
+        let toc = Toc::from_offsets(3, &[2000, 150, 700, 1200]).expect("Toc::from_offsets failed");
+        assert_eq!(None, toc.track_offset(2));
+        assert_eq!(Some(150), toc.track_offset(3));
+        assert_eq!(Some(1200), toc.track_offset(5));
+        assert_eq!(None, toc.track_offset(6));
+        assert_eq!(Some(550), toc.track_sectors(3));
+        assert_eq!(Some(800), toc.track_sectors(5));
+        assert_eq!(None, toc.track_sectors(6));
+    }
+
+    #[test]
+    fn toc_parse_display_roundtrip() {
+        let toc_str =
+            "1 11 242457 150 44942 61305 72755 96360 130485 147315 164275 190702 205412 220437";
+        let toc: Toc = toc_str.parse().expect("parsing TOC failed");
+        assert_eq!("lSOVc5h6IXSuzcamJS1Gp4_tRuA-", toc.id());
+        assert_eq!(toc_str, toc.to_string());
+    }
+
+    #[test]
+    fn toc_parse_minimal() {
+        let toc: Toc = "1 1 44942 150".parse().expect("parsing TOC failed");
+        assert_eq!("ANJa4DGYN_ktpzOwvVPtcjwP7mE-", toc.id());
+        assert_eq!("1 1 44942 150", toc.to_string());
+    }
+
+    #[test]
+    fn toc_parse_error_position() {
+        match "1 2 242457 150 a".parse::<Toc>() {
+            Err(DiscError::ParseInt { position, .. }) => assert_eq!(4, position),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn toc_parse_error_too_many_offsets() {
+        match "1 2 242457 150 200 300".parse::<Toc>() {
+            Err(DiscError::InvalidToc { kind, .. }) => {
+                assert_eq!(TocErrorKind::TooManyOffsets, kind)
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn toc_parse_error_illegal_track_limits() {
+        for toc in &[
+            "1 -1 100 150",
+            "-2147483648 0 100 150",
+            "0 1 100 150",
+            "2 1 100 150",
+        ] {
+            match toc.parse::<Toc>() {
+                Err(DiscError::InvalidToc { kind, .. }) => {
+                    assert_eq!(TocErrorKind::IllegalTrackLimits, kind, "{}", toc)
+                }
+                result => panic!("unexpected result {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "TOC string contains too many offsets")]
+    fn toc_parse_too_many_offsets() {
+        "1 2 242457 150 200 300"
+            .parse::<Toc>()
+            .expect("parsing TOC failed");
+    }
+
+    #[test]
+    #[should_panic(expected = "Number of offsets 1 does not match track count 2")]
+    fn toc_parse_missing_offsets() {
+        "1 2 242457 150".parse::<Toc>().expect("parsing TOC failed");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid TOC string")]
+    fn toc_parse_not_enough_elements() {
+        "1".parse::<Toc>().expect("parsing TOC failed");
+    }
+
+    #[test]
+    #[cfg(feature = "libdiscid")]
+    fn toc_first_track_not_one_matches_libdiscid() {
+        use crate::DiscId;
+
+        let offsets = [
+            206535, 150, 18901, 39738, 59557, 79152, 100126, 124833, 147278, 166336, 182560,
+        ];
+        let toc = Toc::from_offsets(3, &offsets).expect("invalid TOC");
+        let disc = DiscId::put(3, &offsets).expect("DiscId::put() failed");
+        assert_eq!(disc.freedb_id(), toc.freedb_id());
+        assert_eq!(disc.id(), toc.id());
+    }
+}
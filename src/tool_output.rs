@@ -0,0 +1,364 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Parsers for the TOC output of common command line tools.
+//!
+//! The following formats are supported:
+//!
+//! - `cdparanoia -Q`: [`parse_cdparanoia`]
+//! - `cd-info` from libcdio: [`parse_cd_info`]
+//! - `cdrecord -toc` (or `wodim -toc`): [`parse_cdrecord`]
+//!
+//! All parsers return a [`Toc`]. Use [`Toc::offsets_with_lead_out`] to get the offsets in the
+//! layout expected by [`DiscId::put`].
+//!
+//! Data tracks are handled the same way as by libdiscid: If the last track is a data track,
+//! as it is the case for enhanced CDs, it is not part of the TOC and the lead-out is placed
+//! 11400 sectors before the data track.
+//!
+//! # Examples
+//!
+//! ```
+//! use discid::tool_output;
+//!
+//! let output = "
+//! first: 1 last 2
+//! track:   1 lba:         0 (        0) 00:02:00 adr: 1 control: 0 mode: 0
+//! track:   2 lba:     18751 (    75004) 04:12:01 adr: 1 control: 0 mode: 0
+//! track:lout lba:     39588 (   158352) 08:49:63 adr: 1 control: 0 mode: -1
+//! ";
+//!
+//! let toc = tool_output::parse_cdrecord(output).expect("parsing cdrecord output failed");
+//! assert_eq!(vec![39738, 150, 18901], toc.offsets_with_lead_out());
+//! ```
+//!
+//! [`parse_cdparanoia`]: ./fn.parse_cdparanoia.html
+//! [`parse_cd_info`]: ./fn.parse_cd_info.html
+//! [`parse_cdrecord`]: ./fn.parse_cdrecord.html
+//! [`Toc`]: ../struct.Toc.html
+//! [`Toc::offsets_with_lead_out`]: ../struct.Toc.html#method.offsets_with_lead_out
+//! [`DiscId::put`]: ../struct.DiscId.html#method.put
+
+use crate::log;
+use crate::toc::{DATA_TRACK_GAP, LEAD_IN};
+use crate::{DiscError, Toc};
+
+/// A track as listed by one of the tools, with the start given as LBA (without lead-in).
+struct ToolTrack {
+    number: i32,
+    lba: i32,
+    is_data: bool,
+}
+
+/// Parses the output of `cdparanoia -Q`.
+///
+/// cdparanoia only lists audio tracks, hence data tracks are not part of the TOC. For
+/// enhanced CDs the lead-out is placed after the last audio track, which gives the same
+/// result as libdiscid. For mixed mode CDs, which start with a data track, the ID differs
+/// from the one calculated by libdiscid.
+pub fn parse_cdparanoia(output: &str) -> Result<Toc, DiscError> {
+    let mut tracks = Vec::new();
+    let mut lead_out = 0;
+    for (i, line) in output.lines().enumerate() {
+        let line_num = i + 1;
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        // e.g. "  1.    18751 [04:10.01]        0 [00:00.00]    no   no  2"
+        let number = match tokens.first().and_then(|t| t.strip_suffix('.')) {
+            Some(number) if tokens.len() >= 4 => number,
+            _ => continue,
+        };
+        let number = parse_int(number, line_num)?;
+        let length = log::parse_sector(tokens[1], line_num)?;
+        let lba = log::parse_sector(tokens[3], line_num)?;
+        tracks.push(ToolTrack {
+            number,
+            lba,
+            is_data: false,
+        });
+        lead_out = lba
+            .checked_add(length)
+            .ok_or_else(|| DiscError::parse(line_num, "Track ends after maximum sector"))?;
+    }
+    toc_from_tracks(&tracks, lead_out)
+}
+
+/// Parses the output of `cd-info` from libcdio.
+///
+/// The TOC is read from the `CD-ROM Track List` section. Tracks of a type other than `audio`
+/// are considered data tracks.
+pub fn parse_cd_info(output: &str) -> Result<Toc, DiscError> {
+    let mut tracks = Vec::new();
+    let mut lead_out = None;
+    let mut in_track_list = false;
+    for (i, line) in output.lines().enumerate() {
+        let line_num = i + 1;
+        if line.starts_with("CD-ROM Track List") {
+            in_track_list = true;
+            continue;
+        }
+        if !in_track_list {
+            continue;
+        }
+        // e.g. "  1: 00:02:00  000000 audio  false  no    2        no"
+        let (number, rest) = match line.trim().split_once(':') {
+            Some((number, rest)) if number.bytes().all(|b| b.is_ascii_digit()) => (number, rest),
+            _ => continue,
+        };
+        let tokens: Vec<&str> = rest.split_whitespace().collect();
+        if tokens.len() < 3 {
+            return Err(DiscError::parse(line_num, "Invalid track line"));
+        }
+        let lba = log::parse_sector(tokens[1], line_num)?;
+        match tokens[2] {
+            "leadout" => {
+                lead_out = Some(lba);
+                break;
+            }
+            track_type => tracks.push(ToolTrack {
+                number: parse_int(number, line_num)?,
+                lba,
+                is_data: track_type != "audio",
+            }),
+        }
+    }
+    let lead_out = lead_out.ok_or_else(|| DiscError::parse(0, "No lead-out found"))?;
+    toc_from_tracks(&tracks, lead_out)
+}
+
+/// Parses the output of `cdrecord -toc`.
+///
+/// Tracks with bit 2 of the control field set are considered data tracks.
+pub fn parse_cdrecord(output: &str) -> Result<Toc, DiscError> {
+    let mut tracks = Vec::new();
+    let mut lead_out = None;
+    for (i, line) in output.lines().enumerate() {
+        let line_num = i + 1;
+        // e.g. "track:   1 lba:         0 (        0) 00:02:00 adr: 1 control: 0 mode: 0"
+        let rest = match line.trim().strip_prefix("track:") {
+            Some(rest) => rest,
+            None => continue,
+        };
+        let tokens: Vec<&str> = rest.split_whitespace().collect();
+        let field = |name: &str| {
+            tokens
+                .iter()
+                .position(|&t| t == name)
+                .and_then(|pos| tokens.get(pos + 1))
+                .ok_or_else(|| DiscError::parse(line_num, &format!("Missing {}", name)))
+        };
+        let lba = log::parse_sector(field("lba:")?, line_num)?;
+        match tokens.first() {
+            Some(&"lout") => {
+                lead_out = Some(lba);
+                break;
+            }
+            Some(number) => tracks.push(ToolTrack {
+                number: parse_int(number, line_num)?,
+                lba,
+                is_data: parse_int(field("control:")?, line_num)? & 4 != 0,
+            }),
+            None => return Err(DiscError::parse(line_num, "Invalid track line")),
+        }
+    }
+    let lead_out = lead_out.ok_or_else(|| DiscError::parse(0, "No lead-out found"))?;
+    toc_from_tracks(&tracks, lead_out)
+}
+
+fn toc_from_tracks(tracks: &[ToolTrack], lead_out: i32) -> Result<Toc, DiscError> {
+    let (tracks, lead_out) = match tracks.split_last() {
+        None => return Err(DiscError::parse(0, "No tracks found")),
+        Some((last, rest)) if last.is_data && !rest.is_empty() => {
+            let lead_out = last
+                .lba
+                .checked_sub(DATA_TRACK_GAP)
+                .ok_or_else(|| DiscError::parse(0, "Invalid data track start"))?;
+            (rest, lead_out)
+        }
+        Some(_) => (tracks, lead_out),
+    };
+    let first_track = tracks[0].number;
+    let last_track = tracks[tracks.len() - 1].number;
+    let offsets = tracks
+        .iter()
+        .map(|track| with_lead_in(track.lba))
+        .collect::<Result<_, _>>()?;
+    Toc::new(first_track, last_track, with_lead_in(lead_out)?, offsets)
+}
+
+/// Converts an LBA to a TOC offset by adding the lead-in.
+fn with_lead_in(lba: i32) -> Result<i32, DiscError> {
+    lba.checked_add(LEAD_IN)
+        .ok_or_else(|| DiscError::parse(0, "Disc too long"))
+}
+
+fn parse_int(value: &str, line_num: usize) -> Result<i32, DiscError> {
+    value
+        .parse()
+        .map_err(|_| DiscError::parse(line_num, &format!("Invalid number {:?}", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_cd_info, parse_cdparanoia, parse_cdrecord};
+    use crate::DiscError;
+
+    const TOC: &str = "1 10 206535 150 18901 39738 59557 79152 100126 124833 147278 166336 182560";
+
+    #[test]
+    fn tool_output_cdparanoia() {
+        let output = "cdparanoia III release 10.2 (September 11, 2008)
+
+Table of contents (audio tracks only):
+track        length               begin        copy pre ch
+===========================================================
+  1.    18751 [04:10.01]        0 [00:00.00]    no   no  2
+  2.    20837 [04:37.62]    18751 [04:10.01]    no   no  2
+  3.    19819 [04:24.19]    39588 [08:47.63]    no   no  2
+  4.    19595 [04:21.20]    59407 [13:12.07]    no   no  2
+  5.    20974 [04:39.49]    79002 [17:33.27]    no   no  2
+  6.    24707 [05:29.32]    99976 [22:13.01]    no   no  2
+  7.    22445 [04:59.20]   124683 [27:42.33]    no   no  2
+  8.    19058 [04:14.08]   147128 [32:41.53]    no   no  2
+  9.    16224 [03:36.24]   166186 [36:55.61]    no   no  2
+ 10.    23975 [05:19.50]   182410 [40:32.10]    no   no  2
+TOTAL  206385 [45:51.60]    (audio only)
+";
+        let toc = parse_cdparanoia(output).expect("parsing failed");
+        assert_eq!(TOC, toc.to_string());
+        assert_eq!("Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-", toc.id());
+    }
+
+    #[test]
+    fn tool_output_cd_info() {
+        let output = "cd-info version 2.0.0 x86_64-pc-linux-gnu
+CD location   : /dev/cdrom
+CD driver name: GNU/Linux
+__________________________________
+
+Disc mode is listed as: CD-DA
+CD-ROM Track List (1 - 10)
+  #: MSF       LSN    Type   Green? Copy? Channels Premphasis?
+  1: 00:02:00  000000 audio  false  no    2        no
+  2: 04:12:01  018751 audio  false  no    2        no
+  3: 08:49:63  039588 audio  false  no    2        no
+  4: 13:14:07  059407 audio  false  no    2        no
+  5: 17:35:27  079002 audio  false  no    2        no
+  6: 22:15:01  099976 audio  false  no    2        no
+  7: 27:44:33  124683 audio  false  no    2        no
+  8: 32:43:53  147128 audio  false  no    2        no
+  9: 36:57:61  166186 audio  false  no    2        no
+ 10: 40:34:10  182410 audio  false  no    2        no
+170: 45:53:60  206385 leadout (463 MB raw, 463 MB formatted)
+Media Catalog Number (MCN): 0724385583222
+";
+        let toc = parse_cd_info(output).expect("parsing failed");
+        assert_eq!(TOC, toc.to_string());
+    }
+
+    #[test]
+    fn tool_output_cd_info_enhanced_cd() {
+        let output = "CD-ROM Track List (1 - 3)
+  #: MSF       LSN    Type   Green? Copy? Channels Premphasis?
+  1: 00:02:00  000000 audio  false  no    2        no
+  2: 04:12:01  018751 audio  false  no    2        no
+  3: 11:21:63  050988 data   false  no
+170: 13:21:63  059988 leadout (134 MB raw, 117 MB formatted)
+";
+        let toc = parse_cd_info(output).expect("parsing failed");
+        assert_eq!("1 2 39738 150 18901", toc.to_string());
+    }
+
+    #[test]
+    fn tool_output_cdrecord() {
+        let output = "Cdrecord-ProDVD-ProBD-Clone 3.02a09 (x86_64-unknown-linux-gnu)
+scsidev: '/dev/sr0'
+first: 1 last 10
+track:   1 lba:         0 (        0) 00:02:00 adr: 1 control: 0 mode: 0
+track:   2 lba:     18751 (    75004) 04:12:01 adr: 1 control: 0 mode: 0
+track:   3 lba:     39588 (   158352) 08:49:63 adr: 1 control: 0 mode: 0
+track:   4 lba:     59407 (   237628) 13:14:07 adr: 1 control: 0 mode: 0
+track:   5 lba:     79002 (   316008) 17:35:27 adr: 1 control: 0 mode: 0
+track:   6 lba:     99976 (   399904) 22:15:01 adr: 1 control: 0 mode: 0
+track:   7 lba:    124683 (   498732) 27:44:33 adr: 1 control: 0 mode: 0
+track:   8 lba:    147128 (   588512) 32:43:53 adr: 1 control: 0 mode: 0
+track:   9 lba:    166186 (   664744) 36:57:61 adr: 1 control: 0 mode: 0
+track:  10 lba:    182410 (   729640) 40:34:10 adr: 1 control: 0 mode: 0
+track:lout lba:    206385 (   825540) 45:53:60 adr: 1 control: 0 mode: -1
+";
+        let toc = parse_cdrecord(output).expect("parsing failed");
+        assert_eq!(TOC, toc.to_string());
+    }
+
+    #[test]
+    fn tool_output_cdrecord_data_tracks() {
+        let enhanced = "first: 1 last 3
+track:   1 lba:         0 (        0) 00:02:00 adr: 1 control: 0 mode: 0
+track:   2 lba:     18751 (    75004) 04:12:01 adr: 1 control: 0 mode: 0
+track:   3 lba:     50988 (   203952) 11:21:63 adr: 1 control: 4 mode: 1
+track:lout lba:     59988 (   239952) 13:21:63 adr: 1 control: 4 mode: -1
+";
+        let toc = parse_cdrecord(enhanced).expect("parsing failed");
+        assert_eq!("1 2 39738 150 18901", toc.to_string());
+
+        // A mixed mode CD starting with a data track keeps the data track.
+        let mixed_mode = "first: 1 last 2
+track:   1 lba:         0 (        0) 00:02:00 adr: 1 control: 4 mode: 1
+track:   2 lba:     18751 (    75004) 04:12:01 adr: 1 control: 0 mode: 0
+track:lout lba:     39588 (   158352) 08:49:63 adr: 1 control: 0 mode: -1
+";
+        let toc = parse_cdrecord(mixed_mode).expect("parsing failed");
+        assert_eq!("1 2 39738 150 18901", toc.to_string());
+    }
+
+    #[test]
+    fn tool_output_errors() {
+        assert_eq!(
+            Err(DiscError::parse(0, "No tracks found")),
+            parse_cdparanoia("cdparanoia III release 10.2")
+        );
+        assert_eq!(
+            Err(DiscError::parse(0, "No lead-out found")),
+            parse_cd_info("CD-ROM Track List (1 - 1)\n  1: 00:02:00  000000 audio")
+        );
+        assert_eq!(
+            Err(DiscError::parse(1, "Invalid sector \"x\"")),
+            parse_cdrecord("track:   1 lba:         x (        0)")
+        );
+        assert_eq!(
+            Err(DiscError::parse(1, "Missing lba:")),
+            parse_cdrecord("track:   1")
+        );
+    }
+
+    #[test]
+    fn tool_output_sector_out_of_range() {
+        assert_eq!(
+            Err(DiscError::parse(1, "Invalid sector \"2147483647\"")),
+            parse_cdparanoia("  1.    18751 [04:10.01] 2147483647 [00:00.00]    no   no  2")
+        );
+        assert_eq!(
+            Err(DiscError::parse(1, "Track ends after maximum sector")),
+            parse_cdparanoia("  1. 2147483000 [04:10.01] 2147483000 [00:00.00]    no   no  2")
+        );
+        assert_eq!(
+            Err(DiscError::parse(2, "Invalid sector \"-1\"")),
+            parse_cd_info("CD-ROM Track List (1 - 1)\n  1: 00:02:00  -1 audio  false  no")
+        );
+        assert_eq!(
+            Err(DiscError::parse(1, "Invalid sector \"2147483500\"")),
+            parse_cdrecord("track:   1 lba: 2147483500 (        0) 00:02:00 adr: 1 control: 0")
+        );
+    }
+}
@@ -0,0 +1,443 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Support for whipper log files.
+//!
+//! whipper (and its predecessor morituri) write YAML logs. The `TOC` section lists the start
+//! and end sector of each track and the `CD metadata` section contains the MusicBrainz disc
+//! ID calculated by whipper:
+//!
+//! ```text
+//! CD metadata:
+//!   Release:
+//!     Artist: Artist
+//!     Title: Album
+//!   CDDB Disc ID: 0b020f02
+//!   MusicBrainz Disc ID: MS0ykA8iqhwhwey1JFwbNd4IIqE-
+//!
+//! TOC:
+//!   1:
+//!     Start: 00:00:00
+//!     Length: 04:10:01
+//!     Start sector: 0
+//!     End sector: 18750
+//! ```
+//!
+//! Only the parts of the log required to rebuild the TOC are parsed, a full YAML parser is
+//! not required.
+//!
+//! # Examples
+//!
+//! ```
+//! use discid::whipper::WhipperLog;
+//!
+//! let log = WhipperLog::parse("
+//! CD metadata:
+//!   MusicBrainz Disc ID: MS0ykA8iqhwhwey1JFwbNd4IIqE-
+//!
+//! TOC:
+//!   1:
+//!     Start: 00:00:00
+//!     Length: 04:10:01
+//!     Start sector: 0
+//!     End sector: 18750
+//!
+//!   2:
+//!     Start: 04:10:01
+//!     Length: 04:37:62
+//!     Start sector: 18751
+//!     End sector: 39587
+//! ").expect("parsing whipper log failed");
+//!
+//! assert_eq!("1 2 39738 150 18901", log.toc().to_string());
+//! assert!(log.verify().is_ok());
+//! ```
+
+use crate::log::{self, TocRow};
+use crate::{DiscError, DiscIdMismatch, Toc};
+use std::str::FromStr;
+
+/// The disc information extracted from a whipper log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhipperLog {
+    toc: Toc,
+    logged_id: String,
+}
+
+impl WhipperLog {
+    /// Parses a whipper log.
+    ///
+    /// The TOC is rebuilt from the `Start sector` and `End sector` values of the tracks in the
+    /// `TOC` section. A data track of an enhanced CD is not part of the TOC, see
+    /// [`eac::parse_toc`] for details.
+    ///
+    /// The log must contain a `MusicBrainz Disc ID` value. Parsing does not fail if this ID
+    /// does not match the TOC, use [`verify`](#method.verify) to check this.
+    ///
+    /// [`eac::parse_toc`]: ../eac/fn.parse_toc.html
+    pub fn parse(log: &str) -> Result<WhipperLog, DiscError> {
+        let rows = parse_toc_section(log)?;
+        let toc = log::toc_from_rows(&rows)?;
+        let logged_id = log
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("MusicBrainz Disc ID:"))
+            .map(unquote)
+            .ok_or_else(|| DiscError::parse(0, "MusicBrainz Disc ID not found in log"))?;
+
+        Ok(WhipperLog {
+            toc,
+            logged_id: logged_id.to_string(),
+        })
+    }
+
+    /// The TOC of the extracted disc.
+    pub fn toc(&self) -> &Toc {
+        &self.toc
+    }
+
+    /// The MusicBrainz disc ID calculated from the TOC.
+    pub fn id(&self) -> String {
+        self.toc.id()
+    }
+
+    /// The FreeDB disc ID calculated from the TOC.
+    pub fn freedb_id(&self) -> String {
+        self.toc.freedb_id()
+    }
+
+    /// The MusicBrainz disc ID as given in the log.
+    pub fn logged_id(&self) -> &str {
+        &self.logged_id
+    }
+
+    /// Verifies that the MusicBrainz disc ID given in the log matches the TOC.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use discid::whipper::WhipperLog;
+    ///
+    /// let log = WhipperLog::parse("
+    /// MusicBrainz Disc ID: Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-
+    /// TOC:
+    ///   1:
+    ///     Start sector: 0
+    ///     End sector: 18750
+    /// ").expect("parsing whipper log failed");
+    ///
+    /// let mismatch = log.verify().unwrap_err();
+    /// assert_eq!("Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-", mismatch.expected);
+    /// assert_eq!(log.id(), mismatch.calculated);
+    /// ```
+    pub fn verify(&self) -> Result<(), DiscIdMismatch> {
+        let calculated = self.id();
+        if calculated == self.logged_id {
+            Ok(())
+        } else {
+            Err(DiscIdMismatch {
+                expected: self.logged_id.clone(),
+                calculated,
+            })
+        }
+    }
+}
+
+impl FromStr for WhipperLog {
+    type Err = DiscError;
+
+    fn from_str(log: &str) -> Result<WhipperLog, DiscError> {
+        WhipperLog::parse(log)
+    }
+}
+
+/// A track of the `TOC` section, while it is being parsed.
+struct PartialRow {
+    line: usize,
+    number: i32,
+    start: Option<i32>,
+    end: Option<i32>,
+}
+
+/// Parses the `TOC` section of the log.
+fn parse_toc_section(log: &str) -> Result<Vec<TocRow>, DiscError> {
+    let mut rows: Vec<TocRow> = Vec::new();
+    let mut current: Option<PartialRow> = None;
+    let mut in_toc = false;
+    for (i, line) in log.lines().enumerate() {
+        let line_num = i + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) {
+            if in_toc {
+                break;
+            }
+            in_toc = trimmed == "TOC:";
+            continue;
+        }
+        if !in_toc {
+            continue;
+        }
+
+        let (key, value) = trimmed
+            .split_once(':')
+            .ok_or_else(|| DiscError::parse(line_num, "Expected key and value"))?;
+        let value = unquote(value);
+        if value.is_empty() {
+            finish_row(&mut rows, current.take())?;
+            let number = unquote(key)
+                .parse()
+                .map_err(|_| DiscError::parse(line_num, "Invalid track number"))?;
+            current = Some(PartialRow {
+                line: line_num,
+                number,
+                start: None,
+                end: None,
+            });
+            continue;
+        }
+
+        let sector = || log::parse_sector(value, line_num).map(Some);
+        match (current.as_mut(), key) {
+            (Some(row), "Start sector") => row.start = sector()?,
+            (Some(row), "End sector") => row.end = sector()?,
+            _ => {}
+        }
+    }
+    finish_row(&mut rows, current)?;
+
+    if rows.is_empty() {
+        return Err(DiscError::parse(0, "No TOC found in log"));
+    }
+    Ok(rows)
+}
+
+fn finish_row(rows: &mut Vec<TocRow>, row: Option<PartialRow>) -> Result<(), DiscError> {
+    let row = match row {
+        Some(row) => row,
+        None => return Ok(()),
+    };
+    if rows
+        .last()
+        .is_some_and(|previous| previous.number.checked_add(1) != Some(row.number))
+    {
+        return Err(DiscError::parse(row.line, "Invalid track number"));
+    }
+    match (row.start, row.end) {
+        (Some(start), Some(end)) if start <= end => {
+            rows.push(TocRow {
+                number: row.number,
+                start,
+                end,
+            });
+            Ok(())
+        }
+        _ => Err(DiscError::parse(
+            row.line,
+            &format!("Invalid start or end sector for track {}", row.number),
+        )),
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    value
+        .strip_prefix('\'')
+        .and_then(|v| v.strip_suffix('\''))
+        .or_else(|| value.strip_prefix('"').and_then(|v| v.strip_suffix('"')))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WhipperLog;
+    use crate::{DiscError, DiscIdMismatch};
+
+    const LOG: &str = "Log created by: whipper 0.9.0 (internal logger)
+Log creation date: 2020-05-16T12:00:00Z
+
+Ripping phase information:
+  Drive: PLEXTOR DVDR   PX-716A (revision 1.11)
+  Extraction engine: cdparanoia cdparanoia-III 10.2 libcdio 2.0.0 x86_64-pc-linux-gnu
+  Defeat audio cache: true
+  Read offset correction: 30
+
+CD metadata:
+  Release:
+    Artist: Artist
+    Title: Album
+  CDDB Disc ID: 830abf0a
+  MusicBrainz Disc ID: Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-
+  MusicBrainz lookup URL: https://musicbrainz.org/cdtoc/attach?toc=1+10+206535+150+18901+39738+59557+79152+100126+124833+147278+166336+182560&tracks=10&id=Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-
+
+TOC:
+  1:
+    Start: 00:00:00
+    Length: 04:10:01
+    Start sector: 0
+    End sector: 18750
+
+  2:
+    Start: 04:10:01
+    Length: 04:37:62
+    Start sector: 18751
+    End sector: 39587
+
+  3:
+    Start: 08:47:63
+    Length: 04:24:19
+    Start sector: 39588
+    End sector: 59406
+
+  4:
+    Start: 13:12:07
+    Length: 04:21:20
+    Start sector: 59407
+    End sector: 79001
+
+  5:
+    Start: 17:33:27
+    Length: 04:39:49
+    Start sector: 79002
+    End sector: 99975
+
+  6:
+    Start: 22:13:01
+    Length: 05:29:32
+    Start sector: 99976
+    End sector: 124682
+
+  7:
+    Start: 27:42:33
+    Length: 04:59:20
+    Start sector: 124683
+    End sector: 147127
+
+  8:
+    Start: 32:41:53
+    Length: 04:14:08
+    Start sector: 147128
+    End sector: 166185
+
+  9:
+    Start: 36:55:61
+    Length: 03:36:24
+    Start sector: 166186
+    End sector: 182409
+
+  10:
+    Start: 40:32:10
+    Length: 05:19:60
+    Start sector: 182410
+    End sector: 206384
+
+Tracks:
+  1:
+    Filename: ./Artist - Album/01. Artist - Track 1.flac
+    Peak level: 0.988525
+";
+
+    #[test]
+    fn whipper_parse() {
+        let log = WhipperLog::parse(LOG).expect("parsing failed");
+        assert_eq!(
+            "1 10 206535 150 18901 39738 59557 79152 100126 124833 147278 166336 182560",
+            log.toc().to_string()
+        );
+        assert_eq!("Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-", log.logged_id());
+        assert_eq!("Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-", log.id());
+        assert_eq!("830abf0a", log.freedb_id());
+        assert_eq!(Ok(()), log.verify());
+    }
+
+    #[test]
+    fn whipper_verify_mismatch() {
+        let log = LOG.replace("End sector: 206384", "End sector: 206400");
+        let log: WhipperLog = log.parse().expect("parsing failed");
+        assert_eq!(
+            Err(DiscIdMismatch {
+                expected: "Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-".to_string(),
+                calculated: log.id(),
+            }),
+            log.verify()
+        );
+    }
+
+    #[test]
+    fn whipper_parse_quoted() {
+        let log = "CD metadata:
+  MusicBrainz Disc ID: 'MS0ykA8iqhwhwey1JFwbNd4IIqE-'
+TOC:
+  '1':
+    Start sector: 0
+    End sector: 18750
+  '2':
+    Start sector: 18751
+    End sector: 39587
+";
+        let log = WhipperLog::parse(log).expect("parsing failed");
+        assert_eq!("MS0ykA8iqhwhwey1JFwbNd4IIqE-", log.logged_id());
+        assert_eq!(Ok(()), log.verify());
+    }
+
+    #[test]
+    fn whipper_parse_enhanced_cd() {
+        let log = "MusicBrainz Disc ID: MS0ykA8iqhwhwey1JFwbNd4IIqE-
+TOC:
+  1:
+    Start sector: 0
+    End sector: 18750
+  2:
+    Start sector: 18751
+    End sector: 39587
+  3:
+    Start sector: 50988
+    End sector: 59987
+";
+        let log = WhipperLog::parse(log).expect("parsing failed");
+        assert_eq!("1 2 39738 150 18901", log.toc().to_string());
+        assert_eq!(Ok(()), log.verify());
+    }
+
+    #[test]
+    fn whipper_parse_errors() {
+        let error = |log: &str| WhipperLog::parse(log).unwrap_err();
+        assert_eq!(
+            DiscError::parse(0, "No TOC found in log"),
+            error("MusicBrainz Disc ID: x\n")
+        );
+        assert_eq!(
+            DiscError::parse(0, "MusicBrainz Disc ID not found in log"),
+            error("TOC:\n  1:\n    Start sector: 0\n    End sector: 18750\n")
+        );
+        assert_eq!(
+            DiscError::parse(2, "Invalid start or end sector for track 1"),
+            error("TOC:\n  1:\n    Start sector: 0\n")
+        );
+        assert_eq!(
+            DiscError::parse(3, "Invalid sector \"abc\""),
+            error("TOC:\n  1:\n    Start sector: abc\n")
+        );
+        assert_eq!(
+            DiscError::parse(4, "Invalid sector \"2147483647\""),
+            error("TOC:\n  1:\n    Start sector: 0\n    End sector: 2147483647\n")
+        );
+        assert_eq!(
+            DiscError::parse(5, "Invalid track number"),
+            error("TOC:\n  1:\n    Start sector: 0\n    End sector: 18750\n  3:\n")
+        );
+    }
+}
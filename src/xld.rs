@@ -0,0 +1,235 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Support for X Lossless Decoder (XLD) log files.
+//!
+//! XLD logs contain a table with the TOC of the extracted CD, listing start and length of
+//! each track in MSF format as well as the start and end sector:
+//!
+//! ```text
+//! TOC of the extracted CD
+//!      Track |   Start  |  Length  | Start sector | End sector
+//!     ---------------------------------------------------------
+//!         1  | 00:00:00 | 04:10:01 |         0    |    18750
+//!         2  | 04:10:01 | 04:37:62 |     18751    |    39587
+//! ```
+//!
+//! If the rip was verified with AccurateRip the log also contains an
+//! `AccurateRip Summary (DiscID: …)` line. This AccurateRip disc ID, which includes the
+//! FreeDB ID of the disc, is used to verify the parsed TOC.
+//!
+//! # Examples
+//!
+//! ```
+//! use discid::xld::XldLog;
+//!
+//! let log = XldLog::parse("
+//! TOC of the extracted CD
+//!      Track |   Start  |  Length  | Start sector | End sector
+//!     ---------------------------------------------------------
+//!         1  | 00:00:00 | 04:10:01 |         0    |    18750
+//!         2  | 04:10:01 | 04:37:62 |     18751    |    39587
+//!
+//! AccurateRip Summary (DiscID: 0000e3e3-0002626b-0b020f02)
+//! ").expect("parsing XLD log failed");
+//!
+//! assert_eq!("1 2 39738 150 18901", log.toc().to_string());
+//! assert_eq!("0b020f02", log.freedb_id());
+//! assert_eq!(Some("0000e3e3-0002626b-0b020f02"), log.accuraterip_disc_id());
+//! ```
+
+use crate::log;
+use crate::{DiscError, Toc};
+use std::str::FromStr;
+
+/// The disc information extracted from an XLD log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XldLog {
+    toc: Toc,
+    accuraterip_disc_id: Option<String>,
+}
+
+impl XldLog {
+    /// Parses an XLD log.
+    ///
+    /// The first TOC table found in the log is used. A data track of an enhanced CD is not
+    /// part of the TOC, see [`eac::parse_toc`] for details.
+    ///
+    /// If the log contains an AccurateRip summary, the AccurateRip disc ID given there must
+    /// match the TOC, otherwise an error is returned. Like AccurateRip, this check takes the
+    /// data track of an enhanced CD into account.
+    ///
+    /// [`eac::parse_toc`]: ../eac/fn.parse_toc.html
+    pub fn parse(log: &str) -> Result<XldLog, DiscError> {
+        let rows = log::parse_toc_table(log)?;
+        let toc = log::toc_from_rows(&rows)?;
+
+        let mut accuraterip_disc_id = None;
+        if let Some((line_num, ar_id)) = find_accuraterip_disc_id(log) {
+            let calculated = log::accuraterip_id(&rows, &toc)?;
+            if !ar_id.eq_ignore_ascii_case(&calculated.to_string()) {
+                return Err(DiscError::parse(
+                    line_num,
+                    "AccurateRip disc ID does not match TOC",
+                ));
+            }
+            accuraterip_disc_id = Some(ar_id.to_string());
+        }
+
+        Ok(XldLog {
+            toc,
+            accuraterip_disc_id,
+        })
+    }
+
+    /// The TOC of the extracted disc.
+    pub fn toc(&self) -> &Toc {
+        &self.toc
+    }
+
+    /// The MusicBrainz disc ID of the extracted disc.
+    pub fn id(&self) -> String {
+        self.toc.id()
+    }
+
+    /// The FreeDB disc ID of the extracted disc.
+    pub fn freedb_id(&self) -> String {
+        self.toc.freedb_id()
+    }
+
+    /// The disc ID given in the AccurateRip summary, if present.
+    ///
+    /// The ID has the format `xxxxxxxx-yyyyyyyy-zzzzzzzz`, where the last part is the FreeDB
+    /// ID of the disc.
+    pub fn accuraterip_disc_id(&self) -> Option<&str> {
+        self.accuraterip_disc_id.as_deref()
+    }
+}
+
+impl FromStr for XldLog {
+    type Err = DiscError;
+
+    fn from_str(log: &str) -> Result<XldLog, DiscError> {
+        XldLog::parse(log)
+    }
+}
+
+/// Finds the line `AccurateRip Summary (DiscID: …)` and returns its line number and the ID.
+fn find_accuraterip_disc_id(log: &str) -> Option<(usize, &str)> {
+    log.lines().enumerate().find_map(|(i, line)| {
+        let rest = line.trim().strip_prefix("AccurateRip Summary")?;
+        let id = rest.split("DiscID:").nth(1)?;
+        let id = id.trim().trim_end_matches(')').trim();
+        Some((i + 1, id))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::XldLog;
+    use crate::DiscError;
+
+    const LOG: &str = "X Lossless Decoder version 20191004 (152.2)
+
+XLD extraction logfile from 2020-05-16 12:00:00 +0200
+
+Artist / Album
+
+Used drive : PLEXTOR DVDR PX-716A (revision 1.11)
+
+Ripper mode             : XLD Secure Ripper
+Read offset correction  : 30
+
+TOC of the extracted CD
+     Track |   Start  |  Length  | Start sector | End sector
+    ---------------------------------------------------------
+        1  | 00:00:00 | 04:10:01 |         0    |    18750
+        2  | 04:10:01 | 04:37:62 |     18751    |    39587
+        3  | 08:47:63 | 04:24:19 |     39588    |    59406
+        4  | 13:12:07 | 04:21:20 |     59407    |    79001
+        5  | 17:33:27 | 04:39:49 |     79002    |    99975
+        6  | 22:13:01 | 05:29:32 |     99976    |   124682
+        7  | 27:42:33 | 04:59:20 |    124683    |   147127
+        8  | 32:41:53 | 04:14:08 |    147128    |   166185
+        9  | 36:55:61 | 03:36:24 |    166186    |   182409
+       10  | 40:32:10 | 05:19:60 |    182410    |   206384
+
+AccurateRip Summary (DiscID: 001124bc-0089c3df-830abf0a)
+    Track 01 : OK (A2, AR: 9, CRC: 9AA1A5A2)
+    All tracks accurately ripped.
+
+All Tracks
+    Filename : /Users/user/Music/Artist - Album.flac
+";
+
+    #[test]
+    fn xld_parse() {
+        let log = XldLog::parse(LOG).expect("parsing failed");
+        assert_eq!(
+            "1 10 206535 150 18901 39738 59557 79152 100126 124833 147278 166336 182560",
+            log.toc().to_string()
+        );
+        assert_eq!("Wn8eRBtfLDfM0qjYPdxrz.Zjs_U-", log.id());
+        assert_eq!("830abf0a", log.freedb_id());
+        assert_eq!(
+            Some("001124bc-0089c3df-830abf0a"),
+            log.accuraterip_disc_id()
+        );
+    }
+
+    #[test]
+    fn xld_parse_without_accuraterip() {
+        let log = LOG.replace("AccurateRip Summary", "Summary");
+        let log: XldLog = log.parse().expect("parsing failed");
+        assert_eq!("830abf0a", log.freedb_id());
+        assert_eq!(None, log.accuraterip_disc_id());
+    }
+
+    #[test]
+    fn xld_parse_accuraterip_mismatch() {
+        let log = LOG.replace("830abf0a)", "830abf0b)");
+        assert_eq!(
+            Err(DiscError::parse(
+                26,
+                "AccurateRip disc ID does not match TOC"
+            )),
+            XldLog::parse(&log)
+        );
+    }
+
+    #[test]
+    fn xld_parse_enhanced_cd() {
+        let log = "TOC of the extracted CD
+     Track |   Start  |  Length  | Start sector | End sector
+    ---------------------------------------------------------
+        1  | 00:00:00 | 04:10:01 |         0    |    18750
+        2  | 04:10:01 | 04:37:62 |     18751    |    39587
+        3  | 11:19:63 | 02:00:00 |     50988    |    59987
+
+AccurateRip Summary (DiscID: 00013393-0003517b-1a031f03)
+";
+        let log = XldLog::parse(log).expect("parsing failed");
+        assert_eq!("1 2 39738 150 18901", log.toc().to_string());
+        assert_eq!("0b020f02", log.freedb_id());
+    }
+
+    #[test]
+    fn xld_parse_no_toc() {
+        assert_eq!(
+            Err(DiscError::parse(0, "No TOC found in log")),
+            XldLog::parse("X Lossless Decoder version 20191004 (152.2)")
+        );
+    }
+}
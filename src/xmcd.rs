@@ -0,0 +1,561 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Support for xmcd files, the database entry format of FreeDB and other CDDB servers.
+//!
+//! An xmcd file consists of `KEYWORD=value` lines. Long values can be split over several
+//! lines with the same keyword, which are joined when parsing. Lines starting with `#` are
+//! comments, but the comment header contains the track offsets and the disc length, from
+//! which an approximate TOC can be rebuilt:
+//!
+//! ```text
+//! # xmcd
+//! #
+//! # Track frame offsets:
+//! #   150
+//! #   18901
+//! #
+//! # Disc length: 529 seconds
+//! #
+//! DISCID=0b020f02
+//! DTITLE=Artist / Album
+//! DYEAR=2020
+//! DGENRE=Rock
+//! TTITLE0=First Track
+//! TTITLE1=Second Track
+//! EXTD=
+//! EXTT0=
+//! EXTT1=
+//! PLAYORDER=
+//! ```
+//!
+//! # Examples
+//!
+//! ```
+//! use discid::xmcd::XmcdEntry;
+//!
+//! let entry = XmcdEntry::parse("
+//! ## Track frame offsets:
+//! ##   150
+//! ##   18901
+//! ##
+//! ## Disc length: 529 seconds
+//! DISCID=0b020f02
+//! DTITLE=Artist / Album
+//! TTITLE0=First Track
+//! TTITLE1=Second Track
+//! ").expect("parsing xmcd failed");
+//!
+//! assert_eq!(vec!["0b020f02"], entry.disc_ids);
+//! assert_eq!(Some("Artist"), entry.artist());
+//! assert_eq!("Album", entry.album());
+//! assert_eq!("Second Track", entry.track_titles[1]);
+//! assert!(entry.verify().is_ok());
+//! ```
+
+use crate::msf::FRAMES_PER_SECOND;
+#[cfg(feature = "libdiscid")]
+use crate::DiscId;
+use crate::{DiscError, DiscIdMismatch, Toc};
+use std::fmt;
+use std::str::FromStr;
+
+/// Maximum length of a line in an xmcd file, longer values are split over several lines.
+const MAX_LINE_LENGTH: usize = 256;
+
+/// A parsed xmcd database entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XmcdEntry {
+    /// The FreeDB disc IDs of the entry from the `DISCID` lines. An entry can be shared by
+    /// several discs with different IDs.
+    pub disc_ids: Vec<String>,
+
+    /// The disc title from the `DTITLE` line, usually in the form `Artist / Album`.
+    pub title: String,
+
+    /// The release year from the `DYEAR` line.
+    pub year: Option<i32>,
+
+    /// The genre from the `DGENRE` line.
+    pub genre: Option<String>,
+
+    /// The track titles from the `TTITLEn` lines.
+    pub track_titles: Vec<String>,
+
+    /// The extended disc data from the `EXTD` line.
+    pub extended_data: String,
+
+    /// The extended track data from the `EXTTn` lines.
+    pub track_extended_data: Vec<String>,
+
+    /// The approximate TOC rebuilt from the track frame offsets and the disc length given in
+    /// the comment header, if present.
+    ///
+    /// As the disc length is given in seconds, the lead-out is only accurate to the second.
+    /// This is sufficient to calculate the FreeDB ID, but not the MusicBrainz disc ID.
+    pub toc: Option<Toc>,
+}
+
+impl XmcdEntry {
+    /// Parses an xmcd file.
+    ///
+    /// The escape sequences `\n`, `\t` and `\\` in values are replaced. Unknown keywords are
+    /// ignored. Fails if a line is neither a comment nor a `KEYWORD=value` pair or a track
+    /// number is invalid.
+    ///
+    /// If the comment header lists track frame offsets, it must also give the disc length and
+    /// both must form a valid TOC, see [`toc`](#structfield.toc).
+    pub fn parse(xmcd: &str) -> Result<XmcdEntry, DiscError> {
+        let mut entry = XmcdEntry::default();
+        let mut disc_ids = String::new();
+        let mut year = String::new();
+        let mut genre = String::new();
+        let mut offsets = Vec::new();
+        let mut in_offsets = false;
+        let mut disc_length = None;
+        for (i, line) in xmcd.lines().enumerate() {
+            let line_num = i + 1;
+            let line = line.trim_end_matches('\r');
+            if let Some(comment) = line.strip_prefix('#') {
+                let comment = comment.trim();
+                if in_offsets {
+                    if let Ok(offset) = comment.parse() {
+                        offsets.push(offset);
+                        continue;
+                    }
+                    in_offsets = false;
+                }
+                if comment.eq_ignore_ascii_case("Track frame offsets:") {
+                    in_offsets = true;
+                } else if let Some(length) = comment.strip_prefix("Disc length:") {
+                    let lead_out = length
+                        .split_whitespace()
+                        .next()
+                        .and_then(|s| s.parse::<i32>().ok())
+                        .and_then(|seconds| seconds.checked_mul(FRAMES_PER_SECOND));
+                    match lead_out {
+                        Some(lead_out) => disc_length = Some((line_num, lead_out)),
+                        None => return Err(DiscError::parse(line_num, "Invalid disc length")),
+                    }
+                }
+                continue;
+            }
+            in_offsets = false;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (keyword, value) = match line.find('=') {
+                Some(index) => (line[..index].trim(), unescape(&line[index + 1..])),
+                None => return Err(DiscError::parse(line_num, "Expected KEYWORD=value")),
+            };
+            match keyword {
+                "DISCID" => disc_ids.push_str(&value),
+                "DTITLE" => entry.title.push_str(&value),
+                "DYEAR" => year.push_str(&value),
+                "DGENRE" => genre.push_str(&value),
+                "EXTD" => entry.extended_data.push_str(&value),
+                _ => {
+                    if let Some(number) = keyword.strip_prefix("TTITLE") {
+                        let index = track_index(number, line_num)?;
+                        append(&mut entry.track_titles, index, &value);
+                    } else if let Some(number) = keyword.strip_prefix("EXTT") {
+                        let index = track_index(number, line_num)?;
+                        append(&mut entry.track_extended_data, index, &value);
+                    }
+                }
+            }
+        }
+
+        entry.disc_ids = disc_ids
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect();
+        entry.year = year.trim().parse().ok();
+        entry.genre = Some(genre.trim().to_string()).filter(|genre| !genre.is_empty());
+        entry.toc = match (offsets.is_empty(), disc_length) {
+            (true, _) => None,
+            (false, None) => return Err(DiscError::parse(0, "Missing disc length")),
+            (false, Some((line_num, lead_out))) => {
+                let last_track = offsets.len() as i32;
+                Some(
+                    Toc::new(1, last_track, lead_out, offsets)
+                        .map_err(|_| DiscError::parse(line_num, "Invalid track offsets"))?,
+                )
+            }
+        };
+        Ok(entry)
+    }
+
+    /// Creates an empty xmcd entry for the given TOC.
+    ///
+    /// The entry contains the FreeDB ID and the TOC of the disc and an empty title for each
+    /// track. The track numbers in xmcd files always start with 1, a TOC starting with another
+    /// track number is renumbered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use discid::xmcd::XmcdEntry;
+    /// use discid::Toc;
+    ///
+    /// let toc: Toc = "1 2 39738 150 18901".parse().expect("parsing TOC failed");
+    /// let entry = XmcdEntry::from_toc(&toc);
+    /// assert!(entry.to_string().contains("# Disc length: 529 seconds\n"));
+    /// assert!(entry.to_string().contains("DISCID=0b020f02\n"));
+    /// ```
+    pub fn from_toc(toc: &Toc) -> XmcdEntry {
+        let tracks = toc.track_count() as usize;
+        XmcdEntry {
+            disc_ids: vec![toc.freedb_id()],
+            track_titles: vec![String::new(); tracks],
+            track_extended_data: vec![String::new(); tracks],
+            // Renumbering the tracks keeps the TOC valid.
+            toc: Toc::new(1, toc.track_count(), toc.lead_out(), toc.offsets().to_vec()).ok(),
+            ..XmcdEntry::default()
+        }
+    }
+
+    /// Creates an empty xmcd entry for a disc read with libdiscid.
+    ///
+    /// See [`from_toc`](#method.from_toc) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use discid::xmcd::XmcdEntry;
+    /// use discid::DiscId;
+    ///
+    /// let disc = DiscId::read(None).expect("Reading disc failed");
+    /// println!("{}", XmcdEntry::from_disc(&disc));
+    /// ```
+    #[cfg(feature = "libdiscid")]
+    pub fn from_disc(disc: &DiscId) -> XmcdEntry {
+        XmcdEntry::from_toc(&disc.toc())
+    }
+
+    /// Checks that the FreeDB ID calculated from the TOC matches one of the disc IDs of the
+    /// entry.
+    ///
+    /// An entry without a TOC can not be verified and always fails with an empty calculated
+    /// disc ID.
+    pub fn verify(&self) -> Result<(), DiscIdMismatch> {
+        let calculated = self.toc.as_ref().map(Toc::freedb_id).unwrap_or_default();
+        if self
+            .disc_ids
+            .iter()
+            .any(|id| !calculated.is_empty() && id.eq_ignore_ascii_case(&calculated))
+        {
+            Ok(())
+        } else {
+            Err(DiscIdMismatch {
+                expected: self.disc_ids.join(","),
+                calculated,
+            })
+        }
+    }
+
+    /// The artist part of the disc title, if the title has the form `Artist / Album`.
+    pub fn artist(&self) -> Option<&str> {
+        self.title
+            .find(" / ")
+            .map(|index| self.title[..index].trim())
+    }
+
+    /// The album part of the disc title.
+    ///
+    /// If the title does not have the form `Artist / Album`, the whole title is returned.
+    pub fn album(&self) -> &str {
+        match self.title.find(" / ") {
+            Some(index) => self.title[index + 3..].trim(),
+            None => self.title.trim(),
+        }
+    }
+}
+
+impl fmt::Display for XmcdEntry {
+    /// Writes the entry in xmcd format.
+    ///
+    /// Values are escaped and values which do not fit into a single line are split over
+    /// several lines. Track titles and extended track data are written for each track of the
+    /// TOC, even if they are empty.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# xmcd")?;
+        writeln!(f, "#")?;
+        if let Some(toc) = &self.toc {
+            writeln!(f, "# Track frame offsets:")?;
+            for offset in toc.offsets() {
+                writeln!(f, "#\t{}", offset)?;
+            }
+            writeln!(f, "#")?;
+            writeln!(
+                f,
+                "# Disc length: {} seconds",
+                toc.lead_out() / FRAMES_PER_SECOND
+            )?;
+            writeln!(f, "#")?;
+        }
+        writeln!(f, "# Revision: 0")?;
+        writeln!(f, "#")?;
+
+        let tracks = self
+            .toc
+            .as_ref()
+            .map_or(0, |toc| toc.track_count() as usize)
+            .max(self.track_titles.len())
+            .max(self.track_extended_data.len());
+        let track_value =
+            |values: &[String], index: usize| values.get(index).cloned().unwrap_or_default();
+        write_value(f, "DISCID", &self.disc_ids.join(","))?;
+        write_value(f, "DTITLE", &self.title)?;
+        let year = self.year.map(|year| year.to_string()).unwrap_or_default();
+        write_value(f, "DYEAR", &year)?;
+        write_value(f, "DGENRE", self.genre.as_deref().unwrap_or_default())?;
+        for i in 0..tracks {
+            write_value(
+                f,
+                &format!("TTITLE{}", i),
+                &track_value(&self.track_titles, i),
+            )?;
+        }
+        write_value(f, "EXTD", &self.extended_data)?;
+        for i in 0..tracks {
+            let value = track_value(&self.track_extended_data, i);
+            write_value(f, &format!("EXTT{}", i), &value)?;
+        }
+        write_value(f, "PLAYORDER", "")
+    }
+}
+
+impl FromStr for XmcdEntry {
+    type Err = DiscError;
+
+    fn from_str(xmcd: &str) -> Result<XmcdEntry, DiscError> {
+        XmcdEntry::parse(xmcd)
+    }
+}
+
+fn track_index(number: &str, line_num: usize) -> Result<usize, DiscError> {
+    number
+        .parse::<usize>()
+        .ok()
+        .filter(|&index| index < 99)
+        .ok_or_else(|| DiscError::parse(line_num, "Invalid track number"))
+}
+
+/// Appends `value` to the entry at `index`, extending `values` as required.
+fn append(values: &mut Vec<String>, index: usize, value: &str) {
+    if values.len() <= index {
+        values.resize(index + 1, String::new());
+    }
+    values[index].push_str(value);
+}
+
+/// Writes `KEYWORD=value` lines, splitting the escaped value to fit the maximum line length.
+fn write_value(f: &mut fmt::Formatter, keyword: &str, value: &str) -> fmt::Result {
+    let max_length = MAX_LINE_LENGTH - keyword.len() - 1;
+    let mut line = String::new();
+    for c in value.chars() {
+        let escaped = match c {
+            '\n' => "\\n".to_string(),
+            '\t' => "\\t".to_string(),
+            '\\' => "\\\\".to_string(),
+            c => c.to_string(),
+        };
+        if line.len() + escaped.len() > max_length {
+            writeln!(f, "{}={}", keyword, line)?;
+            line.clear();
+        }
+        line.push_str(&escaped);
+    }
+    writeln!(f, "{}={}", keyword, line)
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::XmcdEntry;
+    use crate::{DiscError, DiscIdMismatch, Toc};
+
+    const XMCD: &str = "# xmcd
+#
+# Track frame offsets:
+#\t150
+#\t18901
+#
+# Disc length: 529 seconds
+#
+DISCID=0b020f02,0b020f03
+DTITLE=Some Artist / A Very Long Album Title
+DTITLE= (Deluxe Edition)
+DYEAR=2020
+DGENRE=Rock
+TTITLE0=First Track
+TTITLE1=Second\\tTrack
+EXTD=Recorded live\\nat home
+EXTT0=
+EXTT1=Bonus
+PLAYORDER=
+";
+
+    #[test]
+    fn xmcd_parse() {
+        let entry = XmcdEntry::parse(XMCD).expect("parsing failed");
+        assert_eq!(vec!["0b020f02", "0b020f03"], entry.disc_ids);
+        assert_eq!(
+            "Some Artist / A Very Long Album Title (Deluxe Edition)",
+            entry.title
+        );
+        assert_eq!(Some("Some Artist"), entry.artist());
+        assert_eq!("A Very Long Album Title (Deluxe Edition)", entry.album());
+        assert_eq!(Some(2020), entry.year);
+        assert_eq!(Some("Rock".to_string()), entry.genre);
+        assert_eq!(vec!["First Track", "Second\tTrack"], entry.track_titles);
+        assert_eq!("Recorded live\nat home", entry.extended_data);
+        assert_eq!(vec!["", "Bonus"], entry.track_extended_data);
+        assert_eq!(
+            "1 2 39675 150 18901",
+            entry.toc.as_ref().unwrap().to_string()
+        );
+        assert_eq!(Ok(()), entry.verify());
+    }
+
+    #[test]
+    fn xmcd_verify_mismatch() {
+        let entry = XmcdEntry::parse(&XMCD.replace("529 seconds", "530 seconds")).unwrap();
+        assert_eq!(
+            Err(DiscIdMismatch {
+                expected: "0b020f02,0b020f03".to_string(),
+                calculated: "0b021002".to_string(),
+            }),
+            entry.verify()
+        );
+
+        let entry = XmcdEntry::parse("DISCID=0b020f02").unwrap();
+        assert_eq!(None, entry.toc);
+        assert!(entry.verify().is_err());
+    }
+
+    #[test]
+    fn xmcd_write() {
+        let toc: Toc = "1 2 39738 150 18901".parse().unwrap();
+        let mut entry = XmcdEntry::from_toc(&toc);
+        assert_eq!(
+            "# xmcd
+#
+# Track frame offsets:
+#\t150
+#\t18901
+#
+# Disc length: 529 seconds
+#
+# Revision: 0
+#
+DISCID=0b020f02
+DTITLE=
+DYEAR=
+DGENRE=
+TTITLE0=
+TTITLE1=
+EXTD=
+EXTT0=
+EXTT1=
+PLAYORDER=
+",
+            entry.to_string()
+        );
+
+        entry.title = "Artist / Album".to_string();
+        entry.year = Some(2020);
+        entry.track_titles[0] = "A\\B\tC\nD".to_string();
+        entry.extended_data = "x".repeat(300);
+        let written = entry.to_string();
+        assert!(written.contains("TTITLE0=A\\\\B\\tC\\nD\n"));
+        assert!(written.lines().all(|line| line.len() <= 256));
+        // The lead-out of the parsed TOC is only accurate to the second.
+        let parsed: XmcdEntry = written.parse().unwrap();
+        assert_eq!(entry.track_titles, parsed.track_titles);
+        assert_eq!(entry.extended_data, parsed.extended_data);
+        assert_eq!(written, parsed.to_string());
+    }
+
+    #[test]
+    fn xmcd_write_renumbered() {
+        let toc = Toc::new(3, 4, 39738, vec![150, 18901]).unwrap();
+        let entry = XmcdEntry::from_toc(&toc);
+        assert_eq!(Some(1), entry.toc.as_ref().map(Toc::first_track));
+        assert_eq!(Ok(()), entry.verify());
+    }
+
+    #[test]
+    fn xmcd_parse_without_artist() {
+        let entry: XmcdEntry = "DTITLE=Album\r\nDYEAR=\r\n"
+            .parse()
+            .expect("parsing failed");
+        assert_eq!(None, entry.artist());
+        assert_eq!("Album", entry.album());
+        assert_eq!(None, entry.year);
+        assert_eq!(None, entry.genre);
+        assert!(entry.disc_ids.is_empty());
+    }
+
+    #[test]
+    fn xmcd_parse_errors() {
+        assert_eq!(
+            Err(DiscError::parse(2, "Expected KEYWORD=value")),
+            XmcdEntry::parse("DISCID=0b020f02\nDTITLE")
+        );
+        assert_eq!(
+            Err(DiscError::parse(1, "Invalid track number")),
+            XmcdEntry::parse("TTITLEx=Title")
+        );
+        assert_eq!(
+            Err(DiscError::parse(0, "Missing disc length")),
+            XmcdEntry::parse("# Track frame offsets:\n#\t150\n")
+        );
+        assert_eq!(
+            Err(DiscError::parse(3, "Invalid disc length")),
+            XmcdEntry::parse("# Track frame offsets:\n#\t150\n# Disc length: long\n")
+        );
+        assert_eq!(
+            Err(DiscError::parse(3, "Invalid disc length")),
+            XmcdEntry::parse("# Track frame offsets:\n#\t150\n# Disc length: 99999999 seconds\n")
+        );
+        assert_eq!(
+            Err(DiscError::parse(4, "Invalid track offsets")),
+            XmcdEntry::parse(
+                "# Track frame offsets:\n#\t150\n#\t18901\n# Disc length: 2 seconds\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,354 @@
+// Copyright (C) 2019-2020 Philipp Wolfer <ph.wolfer@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A minimal XML parser for the responses of web services.
+//!
+//! Only the subset of XML used by the supported web services is handled: elements,
+//! attributes, text, CDATA sections and the predefined and numeric character references.
+//! The XML declaration, processing instructions, comments and the document type declaration
+//! are skipped. Namespaces are not resolved, element and attribute names are kept as written.
+
+use crate::DiscError;
+
+/// Maximum nesting depth of elements, to avoid overflowing the stack on malicious input.
+const MAX_DEPTH: usize = 128;
+
+/// An XML element with its attributes and content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+}
+
+/// A node inside an element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Node {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    /// The value of the attribute `name`, if present.
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// All child elements named `name`.
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter_map(move |node| match node {
+            Node::Element(element) if element.name == name => Some(element),
+            _ => None,
+        })
+    }
+
+    /// The first child element named `name`.
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find_map(|node| match node {
+            Node::Element(element) if element.name == name => Some(element),
+            _ => None,
+        })
+    }
+
+    /// The concatenated text content of the element, without descendant elements.
+    pub fn text(&self) -> String {
+        self.children
+            .iter()
+            .filter_map(|node| match node {
+                Node::Text(text) => Some(text.as_str()),
+                Node::Element(_) => None,
+            })
+            .collect()
+    }
+
+    /// The text content of the first child element named `name`.
+    pub fn child_text(&self, name: &str) -> Option<String> {
+        self.child(name).map(Element::text)
+    }
+}
+
+/// Parses an XML document and returns its root element.
+pub(crate) fn parse(document: &str) -> Result<Element, DiscError> {
+    let mut parser = Parser {
+        input: document,
+        position: 0,
+        depth: 0,
+    };
+    parser.skip_misc()?;
+    if !parser.rest().starts_with('<') {
+        return Err(parser.error("Expected root element"));
+    }
+    let root = parser.element()?;
+    parser.skip_misc()?;
+    if !parser.rest().is_empty() {
+        return Err(parser.error("Unexpected content after root element"));
+    }
+    Ok(root)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn line(&self) -> usize {
+        self.input[..self.position].matches('\n').count() + 1
+    }
+
+    fn error(&self, message: &str) -> DiscError {
+        DiscError::parse(self.line(), message)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skips everything up to and including `end`.
+    fn skip_past(&mut self, end: &str) -> Result<&'a str, DiscError> {
+        let rest = self.rest();
+        match rest.find(end) {
+            Some(index) => {
+                self.position += index + end.len();
+                Ok(&rest[..index])
+            }
+            None => Err(self.error(&format!("Missing {}", end))),
+        }
+    }
+
+    /// Skips whitespace, the XML declaration, processing instructions, comments and the
+    /// document type declaration.
+    fn skip_misc(&mut self) -> Result<(), DiscError> {
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<!") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, DiscError> {
+        let rest = self.rest();
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '/' || c == '>' || c == '=')
+            .unwrap_or(rest.len());
+        if end == 0 {
+            return Err(self.error("Expected name"));
+        }
+        self.position += end;
+        Ok(&rest[..end])
+    }
+
+    /// Parses an element, starting at its `<`.
+    fn element(&mut self) -> Result<Element, DiscError> {
+        self.position += 1;
+        let name = self.name()?.to_string();
+        let mut attributes = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.position += 2;
+                return Ok(Element {
+                    name,
+                    attributes,
+                    children: Vec::new(),
+                });
+            } else if rest.starts_with('>') {
+                self.position += 1;
+                break;
+            }
+            let key = self.name()?.to_string();
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(self.error("Expected '=' after attribute name"));
+            }
+            self.position += 1;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(quote @ '"') | Some(quote @ '\'') => quote,
+                _ => return Err(self.error("Expected quoted attribute value")),
+            };
+            self.position += 1;
+            let value = self.skip_past(&quote.to_string())?;
+            attributes.push((key, self.unescape(value)?));
+        }
+
+        let mut children = Vec::new();
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.position += 2;
+                let end_name = self.name()?;
+                if end_name != name {
+                    return Err(self.error(&format!(
+                        "Expected end of element {}, found {}",
+                        name, end_name
+                    )));
+                }
+                self.skip_whitespace();
+                self.skip_past(">")?;
+                return Ok(Element {
+                    name,
+                    attributes,
+                    children,
+                });
+            } else if rest.starts_with("<![CDATA[") {
+                self.position += 9;
+                let text = self.skip_past("]]>")?;
+                children.push(Node::Text(text.to_string()));
+            } else if rest.starts_with("<!--") || rest.starts_with("<?") {
+                self.skip_misc()?;
+            } else if rest.starts_with('<') {
+                if self.depth == MAX_DEPTH {
+                    return Err(self.error("Elements nested too deeply"));
+                }
+                self.depth += 1;
+                let child = self.element()?;
+                self.depth -= 1;
+                children.push(Node::Element(child));
+            } else if rest.is_empty() {
+                return Err(self.error(&format!("Missing end of element {}", name)));
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                self.position += end;
+                children.push(Node::Text(self.unescape(&rest[..end])?));
+            }
+        }
+    }
+
+    /// Replaces character references in `text`.
+    fn unescape(&self, text: &str) -> Result<String, DiscError> {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find('&') {
+            result.push_str(&rest[..start]);
+            rest = &rest[start + 1..];
+            let end = rest
+                .find(';')
+                .ok_or_else(|| self.error("Unterminated character reference"))?;
+            let reference = &rest[..end];
+            let c = match reference {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => reference
+                    .strip_prefix("#x")
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| reference.strip_prefix('#').map(str::parse))
+                    .and_then(Result::ok)
+                    .and_then(std::char::from_u32),
+            };
+            match c {
+                Some(c) => result.push(c),
+                None => {
+                    return Err(self.error(&format!("Invalid character reference &{};", reference)))
+                }
+            }
+            rest = &rest[end + 1..];
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Node};
+    use crate::DiscError;
+
+    #[test]
+    fn xml_parse() {
+        let doc = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- comment -->
+<root a="1" b='x &amp; y'>
+  <item id="one">First &lt;1&gt;</item>
+  <item id="two"><![CDATA[<second>]]></item>
+  <empty/>
+  <ns:other>&#65;&#x42;</ns:other>
+</root>
+"#;
+        let root = parse(doc).unwrap();
+        assert_eq!("root", root.name);
+        assert_eq!(Some("1"), root.attr("a"));
+        assert_eq!(Some("x & y"), root.attr("b"));
+        assert_eq!(None, root.attr("c"));
+        let items: Vec<_> = root.children("item").collect();
+        assert_eq!(2, items.len());
+        assert_eq!(Some("two"), items[1].attr("id"));
+        assert_eq!(vec![Node::Text("First <1>".to_string())], items[0].children);
+        assert_eq!(vec![Node::Text("<second>".to_string())], items[1].children);
+        assert_eq!(
+            vec![Node::Text("AB".to_string())],
+            root.children("ns:other").next().unwrap().children
+        );
+        assert!(root.children("empty").next().unwrap().children.is_empty());
+        assert!(matches!(root.children[0], Node::Text(_)));
+    }
+
+    #[test]
+    fn xml_parse_errors() {
+        assert_eq!(
+            Err(DiscError::parse(1, "Expected root element")),
+            parse("text")
+        );
+        assert_eq!(
+            Err(DiscError::parse(2, "Expected end of element a, found b")),
+            parse("<a>\n</b>")
+        );
+        assert_eq!(
+            Err(DiscError::parse(1, "Missing end of element a")),
+            parse("<a>")
+        );
+        assert_eq!(
+            Err(DiscError::parse(1, "Invalid character reference &foo;")),
+            parse("<a>&foo;</a>")
+        );
+        assert_eq!(
+            Err(DiscError::parse(1, "Unexpected content after root element")),
+            parse("<a/><b/>")
+        );
+        assert_eq!(
+            Err(DiscError::parse(1, "Elements nested too deeply")),
+            parse(&"<a>".repeat(200_000))
+        );
+    }
+
+    #[test]
+    fn xml_parse_max_depth() {
+        let document = format!("{}{}", "<a>".repeat(129), "</a>".repeat(129));
+        assert!(parse(&document).is_ok());
+        let document = format!("{}{}", "<a>".repeat(130), "</a>".repeat(130));
+        assert!(parse(&document).is_err());
+    }
+}